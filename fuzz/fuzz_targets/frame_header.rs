@@ -0,0 +1,12 @@
+#![no_main]
+
+use bang_mp3::FrameHeader;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let header = FrameHeader::read(data);
+    if let Some(bytes) = data.first_chunk() {
+        // The sync check must never reject a header that parses
+        assert!(header.is_err() || FrameHeader::is_plausible_sync(bytes));
+    }
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use bang_mp3::{FrameIter, probe};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(probe) = probe(data) else {
+        return;
+    };
+    if let Ok((_, iter)) = FrameIter::new(&data[probe.first_frame_offset..]) {
+        let mut offset = probe.first_frame_offset;
+        for frame in iter.lossy() {
+            let frame = frame.unwrap();
+            offset += frame.header.frame_bytes;
+            assert!(offset <= data.len());
+        }
+    }
+});
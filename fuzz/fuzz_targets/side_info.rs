@@ -0,0 +1,9 @@
+#![no_main]
+
+use bang_mp3::Frame;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Reads the header and side info of a single frame
+    let _ = Frame::read(data);
+});
@@ -0,0 +1,19 @@
+#![no_main]
+
+use bang_mp3::{FirstFrame, Frame};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Reads the Xing/Info and LAME or VBRI headers, if present
+    match Frame::read_first(data) {
+        Ok(FirstFrame::Vbr(header, vbr_info)) => {
+            let _ = vbr_info.byte_offset_for_fraction(0.5);
+            let _ = vbr_info.time_for_byte(header.frame_bytes);
+        }
+        Ok(FirstFrame::Vbri(header, vbri_info)) => {
+            let _ = vbri_info.byte_offset_for_fraction(0.5);
+            let _ = vbri_info.time_for_byte(header.frame_bytes);
+        }
+        _ => {}
+    }
+});
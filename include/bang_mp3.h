@@ -0,0 +1,63 @@
+/* C bindings for bang_mp3, built with
+ * `cargo rustc --lib --release --features ffi --crate-type cdylib` as
+ * libbang_mp3.so/.dylib/.dll, or with `--crate-type staticlib` as
+ * libbang_mp3.a. Keep in sync with src/ffi.rs. */
+
+#ifndef BANG_MP3_H
+#define BANG_MP3_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define BANG_MP3_OK 0
+/* Returned by bang_mp3_next_frame after the last frame. */
+#define BANG_MP3_DONE (-1)
+#define BANG_MP3_NULL_ARGUMENT (-2)
+
+/* Error codes, one for each DecodingError */
+#define BANG_MP3_UNEXPECTED_END_OF_STREAM 1
+#define BANG_MP3_INVALID_FRAME_HEADER 2
+#define BANG_MP3_UNSUPPORTED_VERSION 3
+#define BANG_MP3_UNSUPPORTED_LAYER 4
+#define BANG_MP3_UNSUPPORTED_BITRATE 5
+#define BANG_MP3_UNSUPPORTED_SAMPLING_RATE 6
+#define BANG_MP3_UNSUPPORTED_EMPHASIS 7
+#define BANG_MP3_INVALID_BLOCK_TYPE 8
+#define BANG_MP3_CRC_MISMATCH 9
+#define BANG_MP3_TRUNCATED_FINAL_FRAME 10
+
+/* An open file, which owns a copy of its data. */
+typedef struct BangMp3 BangMp3;
+
+typedef struct BangMp3FrameInfo {
+    /* Byte offset of the frame in the file. */
+    uint64_t offset;
+    uint32_t frame_bytes;
+    /* In kbit/s. */
+    uint32_t bitrate;
+    uint32_t sampling_rate;
+    uint32_t samples;
+    uint32_t channels;
+} BangMp3FrameInfo;
+
+/* Opens the file in `data`, which is copied. Returns NULL and sets `*error`,
+ * if `error` isn't NULL, to the error code if no frames are found. */
+BangMp3 *bang_mp3_open(const uint8_t *data, size_t len, int32_t *error);
+
+/* Reads the next audio frame into `*info`, skipping over damage. Returns
+ * BANG_MP3_OK, BANG_MP3_DONE after the last frame, or an error code, after
+ * which reading can continue with the next frame. */
+int32_t bang_mp3_next_frame(BangMp3 *file, BangMp3FrameInfo *info);
+
+/* Closes a file opened with bang_mp3_open. Does nothing for NULL. */
+void bang_mp3_close(BangMp3 *file);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BANG_MP3_H */
@@ -0,0 +1,160 @@
+//! Inspects MP3 files with the bang_mp3 library.
+
+use std::{env, fs, process::ExitCode};
+
+use bang_mp3::{
+    ChannelMode, FirstFrame, FrameHeader, FrameIter, Reader, audio_range, probe, read_metadata,
+    validate,
+};
+
+const USAGE: &str = "\
+Usage: bang-mp3 <command> <file>
+
+Commands:
+  probe     Print the stream format, Xing/VBRI header, LAME tag and tags
+  frames    Print a table of the frames
+  validate  Check the file for damage, exiting with 1 if any is found";
+
+fn print_probe(data: &[u8]) -> Result<(), String> {
+    let probe = probe(data).map_err(|err| err.to_string())?;
+    let reader = Reader::new(data).map_err(|err| err.to_string())?;
+    let first_frame = reader.first_frame();
+    let header = first_frame.header();
+    let channels = if header.channel_mode == ChannelMode::Mono {
+        1
+    } else {
+        2
+    };
+
+    println!("format:         {header}");
+    println!("sampling rate:  {} Hz", header.sampling_rate);
+    println!("channels:       {channels} ({:?})", header.channel_mode);
+    println!("bitrate mode:   {:?}", reader.bitrate_mode());
+    if let Some(bitrate) = reader.average_bitrate() {
+        println!("bitrate:        {} kbit/s", bitrate / 1000);
+    }
+    let samples = reader.total_samples();
+    println!(
+        "duration:       {:.3} s ({samples} samples)",
+        samples as f64 / header.sampling_rate as f64
+    );
+    println!("vbr header:     {:?}", probe.vbr_header);
+    println!("encoder:        {:?}", first_frame.encoder());
+    if let Some(delay) = reader.encoder_delay() {
+        println!("encoder delay:  {delay} samples");
+    }
+    if let FirstFrame::Vbr(_, vbr_info) = first_frame
+        && let Some(lame) = vbr_info.lame()
+    {
+        println!("padding:        {} samples", lame.encoder_padding);
+        let gain = lame.replay_gain;
+        if let Some(track_gain) = gain.track_gain {
+            println!("track gain:     {track_gain:+.1} dB");
+        }
+        if let Some(album_gain) = gain.album_gain {
+            println!("album gain:     {album_gain:+.1} dB");
+        }
+    }
+
+    let audio = audio_range(data);
+    println!(
+        "tags:           {} bytes before the audio, {} after",
+        audio.start,
+        data.len() - audio.end
+    );
+    let metadata = read_metadata(data);
+    let fields = [
+        ("title", metadata.title),
+        ("artist", metadata.artist),
+        ("album", metadata.album),
+        ("year", metadata.year),
+        ("track", metadata.track),
+        ("genre", metadata.genre),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value {
+            println!("{:<15} {value}", format!("{name}:"));
+        }
+    }
+    Ok(())
+}
+
+fn print_frame_row(index: usize, offset: usize, header: &FrameHeader, note: &str) {
+    println!(
+        "{index:>5}  {offset:>8}  {:>5}  {:>6}  {:>3}  {:>3}  {:?}{note}",
+        header.frame_bytes,
+        header.bitrate,
+        if header.padding { "yes" } else { "no" },
+        if header.crc.is_some() { "yes" } else { "no" },
+        header.channel_mode,
+    );
+}
+
+fn print_frames(data: &[u8]) -> Result<(), String> {
+    let start = probe(data)
+        .map_err(|err| err.to_string())?
+        .first_frame_offset;
+    let (first_frame, iter) = FrameIter::new(&data[start..]).map_err(|err| err.to_string())?;
+    println!("index    offset  bytes  kbit/s  pad  crc  mode");
+    let note = match first_frame {
+        FirstFrame::Vbr(..) | FirstFrame::Vbri(..) => "  (header)",
+        FirstFrame::Cbr(_) => "",
+    };
+    print_frame_row(0, start, first_frame.header(), note);
+    for frame in iter.resync().with_positions() {
+        match frame {
+            Ok(frame) => print_frame_row(
+                frame.position.frame_index,
+                start + frame.position.offset,
+                &frame.frame.header,
+                "",
+            ),
+            Err(err) => {
+                println!("{err}");
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_validation(data: &[u8]) -> bool {
+    let report = validate(data);
+    println!("{} frames", report.frames);
+    for issue in &report.issues {
+        println!("{issue:?}");
+    }
+    report.is_valid()
+}
+
+fn main() -> ExitCode {
+    let args: Vec<_> = env::args().skip(1).collect();
+    let [command, path] = &args[..] else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) => {
+            eprintln!("{path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match command.as_str() {
+        "probe" => print_probe(&data),
+        "frames" => print_frames(&data),
+        "validate" => return ExitCode::from(!print_validation(&data) as u8),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{path}: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
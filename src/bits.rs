@@ -0,0 +1,67 @@
+/// Reads fields of any number of bits, MSB first, from a byte slice. Bits
+/// past the end of the slice read as zero, so callers check the length of
+/// what they read up front, as `SideInfo::read` does.
+#[derive(Debug, Clone)]
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    /// The next byte to move into `cache`.
+    next_byte: usize,
+    /// The bits read from `data` but not yet consumed, from the MSB down.
+    cache: u64,
+    cached: u32,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        BitReader {
+            data,
+            next_byte: 0,
+            cache: 0,
+            cached: 0,
+        }
+    }
+
+    fn refill(&mut self) {
+        while self.cached <= 56 {
+            let byte = self.data.get(self.next_byte).copied().unwrap_or(0);
+            self.cache |= (byte as u64) << (56 - self.cached);
+            self.cached += 8;
+            self.next_byte += 1;
+        }
+    }
+
+    /// Reads the next `bits` bits, up to 32.
+    pub fn read(&mut self, bits: u32) -> u32 {
+        debug_assert!(bits <= 32);
+        if bits == 0 {
+            return 0;
+        }
+        self.refill();
+        let value = (self.cache >> (64 - bits)) as u32;
+        self.cache <<= bits;
+        self.cached -= bits;
+        value
+    }
+
+    pub fn read_bool(&mut self) -> bool {
+        self.read(1) == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_reader() {
+        let mut reader = BitReader::new(b"\x89\xAB\xCD\xEF\x01\x23\x45\x67\x89");
+        assert_eq!(reader.read(4), 0x8);
+        assert_eq!(reader.read(8), 0x9A);
+        assert!(reader.read_bool());
+        assert_eq!(reader.read(0), 0);
+        assert_eq!(reader.read(32), 0x79BD_E024);
+        assert_eq!(reader.read(27), 0x345_6789);
+        // Past the end
+        assert_eq!(reader.read(32), 0);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{header::FrameHeader, side_info::SideInfo};
+
+/// What `FrameIter` does with a frame whose CRC doesn't match its header and
+/// side info. Many encoders and stream rippers leave stale CRCs behind, so by
+/// default such frames are accepted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CrcPolicy {
+    /// Yield the frame anyway, counting it in `FrameIter::crc_mismatches`.
+    #[default]
+    Accept,
+    /// Skip the frame, counting it in `FrameIter::skipped_frames`.
+    Skip,
+    /// Yield a `DecodingError::CrcMismatch`.
+    Error,
+}
+
+/// CRC-16 with the polynomial 0x8005 and an initial value of 0xFFFF, as used
+/// by MPEG audio.
+pub(crate) fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0xFFFF, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                crc << 1 ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// The CRC of the side info of the frame starting at `frame`. For Layer III
+/// it covers the last two bytes of the header word and the side info.
+fn side_info_crc(header: &FrameHeader, frame: &[u8]) -> Option<u16> {
+    let side_info_end = header.len() + SideInfo::len(header);
+    let side_info = frame.get(header.len()..side_info_end)?;
+
+    let mut protected = [0; 2 + 32];
+    protected[..2].copy_from_slice(&frame[2..4]);
+    protected[2..2 + side_info.len()].copy_from_slice(side_info);
+    Some(crc16(&protected[..2 + side_info.len()]))
+}
+
+/// CRC-16 with the reflected polynomial 0xA001 and an initial value of 0, as
+/// used by the LAME tag.
+pub(crate) fn lame_crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |mut crc, &byte| {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                crc >> 1 ^ 0xA001
+            } else {
+                crc >> 1
+            };
+        }
+        crc
+    })
+}
+
+/// Whether the CRC of the frame starting at `frame`, if it has one, matches.
+pub(crate) fn crc_matches(header: &FrameHeader, frame: &[u8]) -> bool {
+    match header.crc {
+        Some(crc) => side_info_crc(header, frame) == Some(crc),
+        None => true,
+    }
+}
+
+/// Recomputes the CRC of a frame with one, after its side info was changed.
+pub(crate) fn update_crc(header: &FrameHeader, frame: &mut [u8]) {
+    if header.crc.is_some()
+        && let Some(crc) = side_info_crc(header, frame)
+    {
+        frame[4..6].copy_from_slice(&crc.to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DecodingError, FrameIter};
+
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_crc16() {
+        assert_eq!(crc16(b"123456789"), 0xAEE7);
+    }
+
+    #[test]
+    fn test_lame_crc16() {
+        assert_eq!(lame_crc16(b"123456789"), 0xBB3D);
+    }
+
+    #[test]
+    fn test_crc_matches() {
+        // MPEG-1, 128 kbps, 44100 Hz, mono, with CRC
+        let mut frame = b"\xFF\xFA\x90\xC4\x00\x00".to_vec();
+        frame.extend_from_slice(&[0x12; 17]);
+        frame.resize(417, 0);
+        let header = FrameHeader::read(&frame).unwrap();
+        assert!(!crc_matches(&header, &frame));
+
+        let mut protected = vec![0x90, 0xC4];
+        protected.extend_from_slice(&[0x12; 17]);
+        frame[4..6].copy_from_slice(&crc16(&protected).to_be_bytes());
+        let header = FrameHeader::read(&frame).unwrap();
+        assert!(crc_matches(&header, &frame));
+
+        frame[6] = 0;
+        assert!(!crc_matches(&header, &frame));
+        update_crc(&header, &mut frame);
+        let header = FrameHeader::read(&frame).unwrap();
+        assert!(crc_matches(&header, &frame));
+    }
+
+    #[test]
+    fn test_crc_policy() {
+        let mut data = read("tests/sine_320hz_50ms_vbr_frame1-3.mp3").unwrap();
+        data.truncate(731);
+        // Two protected frames with zeroed side info, the first with a stale CRC
+        let mut protected = vec![0x90, 0xC4];
+        protected.resize(2 + 17, 0);
+        for crc in [0xBEEF, crc16(&protected)] {
+            let start = data.len();
+            data.extend_from_slice(b"\xFF\xFA\x90\xC4");
+            data.extend_from_slice(&u16::to_be_bytes(crc));
+            data.resize(start + 417, 0);
+        }
+
+        let (_, mut iter) = FrameIter::new(&data).unwrap();
+        assert_eq!(iter.by_ref().filter(Result::is_ok).count(), 2);
+        assert_eq!(iter.crc_mismatches(), 1);
+
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        let mut iter = iter.crc_policy(CrcPolicy::Skip);
+        let frames: Vec<_> = iter.by_ref().map(|frame| frame.unwrap()).collect();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].header.crc, Some(crc16(&protected)));
+        assert_eq!(iter.skipped_frames(), 1);
+
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        let mut iter = iter.crc_policy(CrcPolicy::Error);
+        assert_eq!(
+            iter.next().unwrap().err().map(|err| err.error),
+            Some(DecodingError::CrcMismatch)
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use std::{ops::Range, time::Duration};
+
+use crate::{
+    DecodingError, FirstFrame, Frame, FrameHeader, FrameWriter, HeaderIter, XingWriter,
+    lame::{DECODER_DELAY, LameTag},
+    probe,
+    reservoir::priming_start,
+};
+
+/// Copies the frames covering `range` of the file in `data` into a new file,
+/// without re-encoding. Leading ID3v2 tags are kept, and the Xing header is
+/// replaced by one describing the new file. So that the first frame decodes
+/// as in the original, the copy starts early enough to include the frames its
+/// bit reservoir reaches back into, and one more for the overlap. The frames
+/// are written by a `FrameWriter`, which silences that one if its own bit
+/// reservoir reaches back past the start of the copy. If the file
+/// has a LAME tag, `range` counts from the end of its encoder delay, and the
+/// new tag has the delay and padding that trim the copy to `range`. The delay
+/// only has 12 bits though, which a deep bit reservoir can exceed, leaving
+/// some audio from before `range`.
+pub fn cut(data: &[u8], range: Range<Duration>) -> Result<Vec<u8>, DecodingError> {
+    let probe = probe(data)?;
+    let audio = &data[probe.audio_offset..];
+    let (offsets, headers): (Vec<_>, Vec<_>) = HeaderIter::new(audio).unzip();
+    let first_header = headers
+        .first()
+        .ok_or(DecodingError::UnexpectedEndOfStream)?;
+    let samples_per_frame = first_header.samples_per_frame() as u64;
+    let (lame, quality) = match Frame::read_first(&data[probe.first_frame_offset..])? {
+        FirstFrame::Vbr(_, vbr_info) => (vbr_info.lame().cloned(), vbr_info.vbr_scale()),
+        _ => (None, None),
+    };
+
+    // Samples as decoded, which start with the delay and end with the padding
+    let decoded = headers.len() as u64 * samples_per_frame;
+    let (delay, audio_end) = match &lame {
+        Some(lame) => (
+            lame.encoder_delay as u64 + DECODER_DELAY,
+            (decoded + DECODER_DELAY).saturating_sub(lame.encoder_padding as u64),
+        ),
+        None => (0, decoded),
+    };
+    let to_sample = |time: Duration| {
+        let sample = (time.as_secs_f64() * first_header.sampling_rate as f64).round() as u64;
+        (sample + delay).min(audio_end.min(decoded))
+    };
+    let (start_sample, end_sample) = (to_sample(range.start), to_sample(range.end));
+    let first = (start_sample / samples_per_frame) as usize;
+    let end = end_sample.div_ceil(samples_per_frame) as usize;
+    if start_sample >= end_sample {
+        return Err(DecodingError::UnexpectedEndOfStream);
+    }
+    let start = priming_start(audio, &offsets, first).unwrap_or(first);
+
+    // The first frame can't reach back past the new Xing frame
+    let mut frame_writer = FrameWriter::new(Vec::new());
+    frame_writer.break_reservoir();
+    for (offset, header) in offsets[start..end].iter().zip(&headers[start..end]) {
+        frame_writer
+            .write_frame(&audio[*offset..offset + header.frame_bytes])
+            .map_err(|_| DecodingError::InvalidFrameHeader)?;
+    }
+    let frame_sizes = frame_writer.frame_sizes().to_vec();
+    let frames = frame_writer.into_inner();
+    let constant = headers[start..end]
+        .iter()
+        .all(|header| header.bitrate == first_header.bitrate);
+    let mut writer = if constant {
+        XingWriter::new().info()
+    } else {
+        XingWriter::new()
+    };
+    if let Some(quality) = quality {
+        writer = writer.quality(quality);
+    }
+    if let Some(lame) = lame {
+        let skip = start_sample - start as u64 * samples_per_frame;
+        let trim = end as u64 * samples_per_frame - end_sample;
+        writer = writer.lame(LameTag {
+            encoder_delay: skip.saturating_sub(DECODER_DELAY).min(0xFFF) as u16,
+            encoder_padding: (trim + DECODER_DELAY).min(0xFFF) as u16,
+            ..lame
+        });
+    }
+    let xing_frame = writer
+        .with_music_crc(&[&frames])
+        .write(first_header, &frame_sizes)
+        .ok_or(DecodingError::UnsupportedBitrate)?;
+
+    let mut output = data[..probe.first_frame_offset].to_vec();
+    output.extend_from_slice(&xing_frame);
+    output.extend_from_slice(&frames);
+    Ok(output)
+}
+
+/// Concatenates the frames of complete MP3 files into one file, with a Xing
+/// header describing it and the leading ID3v2 tags of the first file. Frames
+/// whose bit reservoir reaches back past the start of their file, as after a
+/// naive cut, would decode from the end of the file before it. They are
+/// turned into silence by clearing their side info. Returns
+/// `InvalidFrameHeader` if the files differ in version, sampling rate or
+/// channel count.
+pub fn join(files: &[&[u8]]) -> Result<Vec<u8>, DecodingError> {
+    let mut writer = FrameWriter::new(Vec::new());
+    let mut first_header: Option<FrameHeader> = None;
+    let mut constant = true;
+    for file in files {
+        let audio = &file[probe(file)?.audio_offset..];
+        writer.break_reservoir();
+        for (offset, header) in HeaderIter::new(audio) {
+            let first_header = first_header.get_or_insert(header);
+            if !first_header.is_consistent_with(&header) {
+                return Err(DecodingError::InvalidFrameHeader);
+            }
+            constant &= header.bitrate == first_header.bitrate;
+            writer
+                .write_frame(&audio[offset..offset + header.frame_bytes])
+                .map_err(|_| DecodingError::InvalidFrameHeader)?;
+        }
+    }
+
+    let first_header = first_header.ok_or(DecodingError::UnexpectedEndOfStream)?;
+    let xing_writer = if constant {
+        XingWriter::new().info()
+    } else {
+        XingWriter::new()
+    };
+    let xing_frame = xing_writer
+        .write(&first_header, writer.frame_sizes())
+        .ok_or(DecodingError::UnsupportedBitrate)?;
+
+    let first_file = files.first().copied().unwrap_or_default();
+    let mut output = first_file[..probe(first_file)?.first_frame_offset].to_vec();
+    output.extend_from_slice(&xing_frame);
+    output.extend_from_slice(&writer.into_inner());
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FirstFrame, FrameIter, Reader, SideInfo, crc::lame_crc16};
+
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_cut() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let frame_duration = Duration::from_secs(1152) / 44100;
+        let clip = cut(&data, frame_duration * 10..frame_duration * 15).unwrap();
+
+        let reader = Reader::new(&clip).unwrap();
+        let FirstFrame::Vbr(_, vbr_info) = reader.first_frame() else {
+            panic!("no Xing header");
+        };
+        let frames = vbr_info.frames().unwrap() as usize;
+        // Frames 10 to 14, and those their bit reservoir reaches back into
+        assert!(frames > 5);
+        assert_eq!(vbr_info.filesize(), Some(clip.len() as u32));
+        assert!(!vbr_info.is_info());
+        assert_eq!(reader.scan_report().frames, frames);
+
+        // The audio frames are copied as they are
+        let (_, original) = FrameIter::new(&data).unwrap();
+        let original: Vec<_> = original.map(|frame| frame.unwrap().main_data).collect();
+        let copied: Vec<_> = reader.map(|frame| frame.unwrap().main_data).collect();
+        let start = original
+            .iter()
+            .position(|frame| *frame == copied[0])
+            .unwrap();
+        assert_eq!(copied, original[start..start + frames]);
+
+        // No frame reaches back past the audio frames before it, into the
+        // Xing frame
+        let mut available = 0;
+        for frame in Reader::new(&clip).unwrap() {
+            let frame = frame.unwrap();
+            assert!(frame.side_info.main_data_begin() as usize <= available);
+            available += frame.main_data.len();
+        }
+
+        assert_eq!(
+            cut(&data, Duration::from_secs(5)..Duration::from_secs(6)),
+            Err(DecodingError::UnexpectedEndOfStream)
+        );
+    }
+
+    #[test]
+    fn test_cut_gapless() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let clip = cut(&data, Duration::ZERO..Duration::from_millis(300)).unwrap();
+        let reader = Reader::new(&clip).unwrap();
+        let FirstFrame::Vbr(header, vbr_info) = reader.first_frame() else {
+            panic!("no Xing header");
+        };
+        // Exactly the 300 ms asked for
+        assert_eq!(reader.total_samples(), 13230);
+
+        // The tag is valid
+        let lame = vbr_info.lame().unwrap();
+        assert_eq!(lame.encoder_delay, 576);
+        assert_eq!(lame.music_length as usize, clip.len());
+        let tag_end = header.len() + SideInfo::len(header) + 120 + LameTag::LEN;
+        assert_eq!(lame.tag_crc, lame_crc16(&clip[..tag_end - 2]));
+        assert_eq!(lame.music_crc, lame_crc16(&clip[header.frame_bytes..]));
+
+        // The end is limited by the padding of the original
+        let clip = cut(&data, Duration::ZERO..Duration::from_secs(9)).unwrap();
+        let original = Reader::new(&data).unwrap().total_samples();
+        assert_eq!(Reader::new(&clip).unwrap().total_samples(), original);
+
+        // Deep into the file, the bit reservoir reaches back further than
+        // the delay can skip
+        let clip = cut(
+            &data,
+            Duration::from_millis(300)..Duration::from_millis(400),
+        )
+        .unwrap();
+        assert!(Reader::new(&clip).unwrap().total_samples() > 4410);
+    }
+}
@@ -1,3 +1,4 @@
+#[allow(dead_code)]
 pub fn play_sound(sound: &str) {
     println!("Playing sound: {}", sound);
 }
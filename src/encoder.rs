@@ -0,0 +1,250 @@
+use crate::{FirstFrame, lame::LameTag};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VbrHeader {
+    /// Xing header, written for VBR streams.
+    Xing,
+    /// Xing-compatible header tagged "Info", written by LAME for CBR streams.
+    Info,
+    /// Fraunhofer VBRI header.
+    Vbri,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitrateMode {
+    Constant,
+    Variable,
+    /// Variable, but targeting an average bitrate (ABR).
+    Average,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncoderInfo<'a> {
+    /// LAME, identified by its tag or by the version string it writes into
+    /// the ancillary data of CBR streams without an Info header.
+    Lame {
+        version: &'a str,
+        vbr_header: VbrHeader,
+    },
+    /// FFmpeg (libavformat/libavcodec), which writes a LAME-compatible tag.
+    Ffmpeg {
+        version: &'a str,
+        vbr_header: VbrHeader,
+    },
+    /// Fraunhofer encoders write VBRI headers.
+    Fraunhofer,
+    Unknown {
+        vbr_header: VbrHeader,
+    },
+}
+
+impl EncoderInfo<'_> {
+    pub fn vbr_header(&self) -> VbrHeader {
+        match self {
+            EncoderInfo::Lame { vbr_header, .. }
+            | EncoderInfo::Ffmpeg { vbr_header, .. }
+            | EncoderInfo::Unknown { vbr_header } => *vbr_header,
+            EncoderInfo::Fraunhofer => VbrHeader::Vbri,
+        }
+    }
+}
+
+/// Takes the version characters following `prefix`, e.g. `3.100` from `LAME3.100UUUU`.
+fn version_after<'a>(data: &'a [u8], prefix: &[u8]) -> Option<&'a str> {
+    let rest = data.strip_prefix(prefix)?;
+    let len = rest
+        .iter()
+        .position(|b| !(b.is_ascii_digit() || b.is_ascii_lowercase() || *b == b'.'))
+        .unwrap_or(rest.len());
+    str::from_utf8(&rest[..len]).ok()
+}
+
+fn from_lame_tag(lame: &LameTag, vbr_header: VbrHeader) -> EncoderInfo<'_> {
+    let encoder = &lame.encoder[..];
+    // Some LAME 3.9x releases abbreviate the signature to e.g. "L3.99r"
+    let short_lame = encoder
+        .starts_with(b"L3")
+        .then(|| version_after(encoder, b"L"));
+    if let Some(version) = version_after(encoder, b"LAME").or(short_lame.flatten()) {
+        EncoderInfo::Lame {
+            version,
+            vbr_header,
+        }
+    } else if let Some(version) =
+        version_after(encoder, b"Lavf").or(version_after(encoder, b"Lavc"))
+    {
+        EncoderInfo::Ffmpeg {
+            version,
+            vbr_header,
+        }
+    } else {
+        EncoderInfo::Unknown { vbr_header }
+    }
+}
+
+impl FirstFrame<'_> {
+    /// How the stream was encoded, going by the LAME tag or else the kind of
+    /// header. `None` for a first frame without a header, in which case only
+    /// scanning the frames can tell, see `Reader::bitrate_mode`.
+    pub fn bitrate_mode(&self) -> Option<BitrateMode> {
+        match self {
+            FirstFrame::Vbr(_, vbr_info) => {
+                let from_tag = match vbr_info.lame().map(|lame| lame.vbr_method) {
+                    Some(1 | 8) => Some(BitrateMode::Constant),
+                    Some(2 | 9) => Some(BitrateMode::Average),
+                    Some(3..=6) => Some(BitrateMode::Variable),
+                    _ => None,
+                };
+                Some(from_tag.unwrap_or(if vbr_info.is_info() {
+                    BitrateMode::Constant
+                } else {
+                    BitrateMode::Variable
+                }))
+            }
+            FirstFrame::Vbri(_, _) => Some(BitrateMode::Variable),
+            FirstFrame::Cbr(_) => None,
+        }
+    }
+
+    /// Identifies the encoder from the Xing/Info/VBRI headers and the LAME
+    /// tag. ID3 tags are not consulted.
+    pub fn encoder(&self) -> EncoderInfo<'_> {
+        match self {
+            FirstFrame::Vbr(_, vbr_info) => {
+                let vbr_header = if vbr_info.is_info() {
+                    VbrHeader::Info
+                } else {
+                    VbrHeader::Xing
+                };
+                match vbr_info.lame() {
+                    Some(lame) => from_lame_tag(lame, vbr_header),
+                    None => EncoderInfo::Unknown { vbr_header },
+                }
+            }
+            FirstFrame::Vbri(_, _) => EncoderInfo::Fraunhofer,
+            FirstFrame::Cbr(frame) => {
+                let vbr_header = VbrHeader::None;
+                let version = frame
+                    .main_data
+                    .windows(4)
+                    .position(|window| window == b"LAME")
+                    .and_then(|start| version_after(&frame.main_data[start..], b"LAME"));
+                match version {
+                    Some(version) if !version.is_empty() => EncoderInfo::Lame {
+                        version,
+                        vbr_header,
+                    },
+                    _ => EncoderInfo::Unknown { vbr_header },
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Frame, FrameIter};
+
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_lame_xing_encoder() {
+        let data = read("tests/sine_320hz_50ms_vbr.mp3").unwrap();
+        let (first_frame, _) = FrameIter::new(&data).unwrap();
+        assert_eq!(
+            first_frame.encoder(),
+            EncoderInfo::Lame {
+                version: "3.100",
+                vbr_header: VbrHeader::Xing,
+            }
+        );
+    }
+
+    #[test]
+    fn test_lame_info_and_ffmpeg_encoder() {
+        let mut data = read("tests/sine_320hz_50ms_vbr_frame0.mp3").unwrap();
+        data[0x15..0x19].copy_from_slice(b"Info");
+        let first_frame = Frame::read_first(&data).unwrap();
+        assert_eq!(first_frame.encoder().vbr_header(), VbrHeader::Info);
+
+        data[0x8d..0x96].copy_from_slice(b"Lavf58.29");
+        let first_frame = Frame::read_first(&data).unwrap();
+        assert_eq!(
+            first_frame.encoder(),
+            EncoderInfo::Ffmpeg {
+                version: "58.29",
+                vbr_header: VbrHeader::Info,
+            }
+        );
+
+        data[0x8d..0x96].copy_from_slice(b"L3.99r\0\0\0");
+        let first_frame = Frame::read_first(&data).unwrap();
+        assert_eq!(
+            first_frame.encoder(),
+            EncoderInfo::Lame {
+                version: "3.99r",
+                vbr_header: VbrHeader::Info,
+            }
+        );
+    }
+
+    #[test]
+    fn test_bitrate_mode() {
+        let mut data = read("tests/sine_320hz_50ms_vbr_frame0.mp3").unwrap();
+        let first_frame = Frame::read_first(&data).unwrap();
+        assert_eq!(first_frame.bitrate_mode(), Some(BitrateMode::Variable));
+
+        // The VBR method nibble of the LAME tag
+        data[0x96] = data[0x96] & 0xF0 | 2;
+        let first_frame = Frame::read_first(&data).unwrap();
+        assert_eq!(first_frame.bitrate_mode(), Some(BitrateMode::Average));
+
+        data[0x96] &= 0xF0;
+        data[0x15..0x19].copy_from_slice(b"Info");
+        let first_frame = Frame::read_first(&data).unwrap();
+        assert_eq!(first_frame.bitrate_mode(), Some(BitrateMode::Constant));
+
+        let data = read("tests/sine_320hz_50ms_vbr_frame1-3.mp3").unwrap();
+        assert_eq!(Frame::read_first(&data).unwrap().bitrate_mode(), None);
+    }
+
+    #[test]
+    fn test_lame_ancillary_encoder() {
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        // Skip the ID3v2 tag
+        let first_frame = Frame::read_first(&data[32..]).unwrap();
+        assert_eq!(
+            first_frame.encoder(),
+            EncoderInfo::Lame {
+                version: "3.98.2",
+                vbr_header: VbrHeader::None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_unknown_encoder() {
+        let data = read("tests/sine_320hz_50ms_vbr_frame1-3.mp3").unwrap();
+        let first_frame = Frame::read_first(&data).unwrap();
+        // LAME also leaves its version in the ancillary data of audio frames
+        assert_eq!(
+            first_frame.encoder(),
+            EncoderInfo::Lame {
+                version: "3.100",
+                vbr_header: VbrHeader::None,
+            }
+        );
+
+        let mut data = read("tests/sine_320hz_50ms_vbr_frame0.mp3").unwrap();
+        data[0x8d..0x96].fill(0);
+        let first_frame = Frame::read_first(&data).unwrap();
+        assert_eq!(
+            first_frame.encoder(),
+            EncoderInfo::Unknown {
+                vbr_header: VbrHeader::Xing,
+            }
+        );
+    }
+}
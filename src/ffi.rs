@@ -0,0 +1,209 @@
+//! C bindings, declared in `include/bang_mp3.h`. These are the only unsafe
+//! code in the crate, and only built with the `ffi` feature.
+
+use std::{ptr, slice};
+
+use crate::{ChannelMode, DecodingError, FrameHeader, FrameIter, Position, probe};
+
+pub const BANG_MP3_OK: i32 = 0;
+/// Returned by `bang_mp3_next_frame` after the last frame.
+pub const BANG_MP3_DONE: i32 = -1;
+pub const BANG_MP3_NULL_ARGUMENT: i32 = -2;
+
+/// The error code for each `DecodingError`, counting from 1.
+fn error_code(error: DecodingError) -> i32 {
+    match error {
+        DecodingError::UnexpectedEndOfStream => 1,
+        DecodingError::InvalidFrameHeader => 2,
+        DecodingError::UnsupportedVersion => 3,
+        DecodingError::UnsupportedLayer => 4,
+        DecodingError::UnsupportedBitrate => 5,
+        DecodingError::UnsupportedSamplingRate => 6,
+        DecodingError::UnsupportedEmphasis => 7,
+        DecodingError::InvalidBlockType => 8,
+        DecodingError::CrcMismatch => 9,
+        DecodingError::TruncatedFinalFrame { .. } => 10,
+    }
+}
+
+/// An open file, which owns a copy of its data.
+pub struct BangMp3 {
+    data: Box<[u8]>,
+    /// Where the next frame starts.
+    position: Position,
+}
+
+/// What `bang_mp3_next_frame` tells about a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct BangMp3FrameInfo {
+    /// Byte offset of the frame in the file.
+    pub offset: u64,
+    pub frame_bytes: u32,
+    /// In kbit/s.
+    pub bitrate: u32,
+    pub sampling_rate: u32,
+    pub samples: u32,
+    pub channels: u32,
+}
+
+/// Opens the file in `data`, which is copied. Returns null and sets `*error`
+/// to the error code if no frames are found. Close the file with
+/// `bang_mp3_close`.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, and `error` must be null or
+/// point to a writable `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bang_mp3_open(
+    data: *const u8,
+    len: usize,
+    error: *mut i32,
+) -> *mut BangMp3 {
+    let set_error = |code| {
+        if !error.is_null() {
+            // SAFETY: the caller passes a writable pointer or null
+            unsafe { *error = code };
+        }
+    };
+    if data.is_null() {
+        set_error(BANG_MP3_NULL_ARGUMENT);
+        return ptr::null_mut();
+    }
+    // SAFETY: the caller passes `len` readable bytes
+    let data: Box<[u8]> = unsafe { slice::from_raw_parts(data, len) }.into();
+    match probe(&data) {
+        Ok(probe) => {
+            set_error(BANG_MP3_OK);
+            let header_frames = (probe.audio_offset != probe.first_frame_offset) as usize;
+            let position = Position {
+                offset: probe.audio_offset,
+                frame_index: header_frames,
+            };
+            Box::into_raw(Box::new(BangMp3 { data, position }))
+        }
+        Err(err) => {
+            set_error(error_code(err));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Reads the next audio frame into `*info`, skipping over damage. Returns
+/// `BANG_MP3_OK`, `BANG_MP3_DONE` after the last frame, or an error code,
+/// after which reading can continue with the next frame.
+///
+/// # Safety
+///
+/// `file` must come from `bang_mp3_open` and not be closed, and `info` must
+/// point to a writable `BangMp3FrameInfo`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bang_mp3_next_frame(
+    file: *mut BangMp3,
+    info: *mut BangMp3FrameInfo,
+) -> i32 {
+    // SAFETY: the caller passes an open file
+    let Some(file) = (unsafe { file.as_mut() }) else {
+        return BANG_MP3_NULL_ARGUMENT;
+    };
+    if info.is_null() {
+        return BANG_MP3_NULL_ARGUMENT;
+    }
+
+    let start = file.position;
+    let mut iter = FrameIter::resume(&file.data[start.offset..], start).resync();
+    let result = iter.next();
+    file.position = iter.position();
+    match result {
+        Some(Ok(frame)) => {
+            let header = frame.header;
+            let frame_info = BangMp3FrameInfo {
+                offset: (file.position.offset - header.frame_bytes) as u64,
+                frame_bytes: header.frame_bytes as u32,
+                bitrate: header.bitrate,
+                sampling_rate: header.sampling_rate,
+                samples: header.samples_per_frame(),
+                channels: if header.channel_mode == ChannelMode::Mono {
+                    1
+                } else {
+                    2
+                },
+            };
+            // SAFETY: the caller passes a writable pointer
+            unsafe { info.write(frame_info) };
+            BANG_MP3_OK
+        }
+        Some(Err(err)) => {
+            // Carry on after the frame that failed
+            if file.position == start {
+                let frame_bytes = FrameHeader::read(&file.data[start.offset..])
+                    .map_or(1, |header| header.frame_bytes);
+                file.position = Position {
+                    offset: (start.offset + frame_bytes).min(file.data.len()),
+                    frame_index: start.frame_index + 1,
+                };
+            }
+            error_code(err.error)
+        }
+        None => BANG_MP3_DONE,
+    }
+}
+
+/// Closes a file opened with `bang_mp3_open`. Does nothing for null.
+///
+/// # Safety
+///
+/// `file` must come from `bang_mp3_open` and not be closed already.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bang_mp3_close(file: *mut BangMp3) {
+    if !file.is_null() {
+        // SAFETY: the file was boxed by `bang_mp3_open`
+        drop(unsafe { Box::from_raw(file) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_ffi() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let mut error = -100;
+        let file = unsafe { bang_mp3_open(data.as_ptr(), data.len(), &mut error) };
+        assert!(!file.is_null());
+        assert_eq!(error, BANG_MP3_OK);
+
+        let mut info = BangMp3FrameInfo {
+            offset: 0,
+            frame_bytes: 0,
+            bitrate: 0,
+            sampling_rate: 0,
+            samples: 0,
+            channels: 0,
+        };
+        let mut frames = 0;
+        while unsafe { bang_mp3_next_frame(file, &mut info) } == BANG_MP3_OK {
+            if frames == 0 {
+                assert_eq!(info.offset, 417);
+                assert_eq!(info.sampling_rate, 44100);
+                assert_eq!(info.samples, 1152);
+                assert_eq!(info.channels, 1);
+            }
+            frames += 1;
+        }
+        assert_eq!(frames, 21);
+        assert_eq!(
+            unsafe { bang_mp3_next_frame(file, &mut info) },
+            BANG_MP3_DONE
+        );
+        unsafe { bang_mp3_close(file) };
+
+        let file = unsafe { bang_mp3_open(b"junk".as_ptr(), 4, &mut error) };
+        assert!(file.is_null());
+        assert_eq!(error, error_code(DecodingError::InvalidFrameHeader));
+        unsafe { bang_mp3_close(ptr::null_mut()) };
+    }
+}
@@ -1,4 +1,6 @@
-use crate::{DecodingError, read_u16, read_u32};
+use std::{fmt, time::Duration};
+
+use crate::{DecodingError, crc::crc16, read_u16, read_u32};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChannelMode {
@@ -13,6 +15,20 @@ pub enum Emphasis {
     None,
     FiftyFifteenMs,
     CCITTJ17,
+    /// The reserved value, which some encoders write anyway. Only accepted in
+    /// `ParseMode::Lenient`, and best treated like `None`.
+    Reserved,
+}
+
+/// How closely headers have to follow the standard to be accepted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Reject reserved field values, and a mode extension outside of joint
+    /// stereo.
+    Strict,
+    /// Accept anything that can be decoded, as real encoders produce.
+    #[default]
+    Lenient,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -23,35 +39,189 @@ pub enum Version {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 pub enum Layer {
     LayerI,
     LayerII,
     LayerIII,
 }
 
+/// Layer III bitrates in kbit/s by the index in the header, with zero for
+/// free format and the invalid index.
+const MPEG1_BITRATES: [u32; 16] = [
+    0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 0,
+];
+/// For MPEG-2 and MPEG-2.5.
+const LSF_BITRATES: [u32; 16] = [
+    0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0,
+];
+
+fn bitrates(version: Version) -> &'static [u32; 16] {
+    if version == Version::MPEG1 {
+        &MPEG1_BITRATES
+    } else {
+        &LSF_BITRATES
+    }
+}
+
+/// For MPEG-1; the LSF versions use half and quarter of these rates
+const SAMPLING_RATES: [u32; 3] = [44100, 48000, 32000];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FrameHeader {
+    raw: u32,
     pub version: Version,
     pub layer: Layer,
     pub bitrate: u32,
     pub sampling_rate: u32,
-    crc: Option<u16>,
+    pub crc: Option<u16>,
     pub frame_bytes: usize,
-    private_bit: bool,
+    pub padding: bool,
+    pub private_bit: bool,
     pub channel_mode: ChannelMode,
-    intensity_stereo: bool,
-    ms_stereo: bool,
-    copyright: bool,
-    original: bool,
-    emphasis: Emphasis,
+    pub intensity_stereo: bool,
+    pub ms_stereo: bool,
+    pub copyright: bool,
+    pub original: bool,
+    pub emphasis: Emphasis,
 }
 
 impl FrameHeader {
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         if self.crc.is_some() { 6 } else { 4 }
     }
 
-    pub fn read(mut bytes: &[u8]) -> Result<FrameHeader, DecodingError> {
+    /// The header word as it appeared in the stream, excluding the CRC.
+    pub fn raw(&self) -> u32 {
+        self.raw
+    }
+
+    /// The header word for the fields, reversing `read`, e.g. after editing
+    /// them. `frame_bytes` follows from the other fields, and so isn't
+    /// written. A bitrate or sampling rate that the version doesn't have
+    /// keeps the bits of `raw`.
+    pub fn write(&self) -> [u8; 4] {
+        let version = match self.version {
+            Version::MPEG1 => 0b11,
+            Version::MPEG2 => 0b10,
+            Version::MPEG2_5 => 0b00,
+        };
+        let layer = match self.layer {
+            Layer::LayerI => 0b11,
+            Layer::LayerII => 0b10,
+            Layer::LayerIII => 0b01,
+        };
+        let bitrate = (1..15)
+            .find(|&index| bitrates(self.version)[index] == self.bitrate)
+            .map_or(self.raw >> 12 & 0b1111, |index| index as u32);
+        let divisor = match self.version {
+            Version::MPEG1 => 1,
+            Version::MPEG2 => 2,
+            Version::MPEG2_5 => 4,
+        };
+        let sampling_rate = SAMPLING_RATES
+            .iter()
+            .position(|&rate| rate / divisor == self.sampling_rate)
+            .map_or(self.raw >> 10 & 0b11, |index| index as u32);
+        let channel_mode = match self.channel_mode {
+            ChannelMode::Stereo => 0b00,
+            ChannelMode::JointStereo => 0b01,
+            ChannelMode::DualChannel => 0b10,
+            ChannelMode::Mono => 0b11,
+        };
+        let mode_extension = if self.channel_mode == ChannelMode::JointStereo {
+            (self.intensity_stereo as u32) | (self.ms_stereo as u32) << 1
+        } else {
+            0
+        };
+        let emphasis = match self.emphasis {
+            Emphasis::None => 0b00,
+            Emphasis::FiftyFifteenMs => 0b01,
+            Emphasis::Reserved => 0b10,
+            Emphasis::CCITTJ17 => 0b11,
+        };
+
+        let word = 0b111_1111_1111 << 21
+            | version << 19
+            | layer << 17
+            | (self.crc.is_none() as u32) << 16
+            | bitrate << 12
+            | sampling_rate << 10
+            | (self.padding as u32) << 9
+            | (self.private_bit as u32) << 8
+            | channel_mode << 6
+            | mode_extension << 4
+            | (self.copyright as u32) << 3
+            | (self.original as u32) << 2
+            | emphasis;
+        word.to_be_bytes()
+    }
+
+    /// The header word followed by the CRC if the frame has one, `len()`
+    /// bytes in all. The CRC is written as it is; see `compute_crc`.
+    pub fn write_with_crc(&self) -> Vec<u8> {
+        let mut bytes = self.write().to_vec();
+        if let Some(crc) = self.crc {
+            bytes.extend_from_slice(&crc.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// The CRC of a frame with this header and `side_info`, e.g. for setting
+    /// `crc` after editing either.
+    pub fn compute_crc(&self, side_info: &[u8]) -> u16 {
+        let mut protected = self.write()[2..].to_vec();
+        protected.extend_from_slice(side_info);
+        crc16(&protected)
+    }
+
+    /// Number of PCM samples per channel that the frame decodes to.
+    pub fn samples_per_frame(&self) -> u32 {
+        match (self.layer, self.version) {
+            (Layer::LayerI, _) => 384,
+            (Layer::LayerII, _) | (Layer::LayerIII, Version::MPEG1) => 1152,
+            (Layer::LayerIII, Version::MPEG2 | Version::MPEG2_5) => 576,
+        }
+    }
+
+    /// Playback time of the frame.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.samples_per_frame() as u64) / self.sampling_rate
+    }
+
+    /// A cheap check that `bytes` start a header `FrameHeader::read` would
+    /// accept, for scanning a stream for the next frame.
+    pub fn is_plausible_sync(bytes: &[u8; 4]) -> bool {
+        let frame_header = u32::from_be_bytes(*bytes);
+        frame_header >> 21 == 0b111_1111_1111
+            // version: not reserved
+            && frame_header >> 19 & 0b11 != 0b01
+            // layer: Layer III
+            && frame_header >> 17 & 0b11 == 0b01
+            // bitrate: neither free format nor the invalid index
+            && !matches!(frame_header >> 12 & 0b1111, 0b0000 | 0b1111)
+            // sampling rate: not reserved
+            && frame_header >> 10 & 0b11 != 0b11
+    }
+
+    /// Whether `next` could follow this header in the same stream. The
+    /// version, layer, sampling rate and number of channels stay fixed within
+    /// a stream, while the bitrate, padding and stereo mode may change from
+    /// frame to frame.
+    pub fn is_consistent_with(&self, next: &FrameHeader) -> bool {
+        self.version == next.version
+            && self.layer == next.layer
+            && self.sampling_rate == next.sampling_rate
+            && (self.channel_mode == ChannelMode::Mono) == (next.channel_mode == ChannelMode::Mono)
+    }
+
+    /// Reads a header in `ParseMode::Lenient`.
+    pub fn read(bytes: &[u8]) -> Result<FrameHeader, DecodingError> {
+        Self::read_with_mode(bytes, ParseMode::Lenient)
+    }
+
+    pub fn read_with_mode(mut bytes: &[u8], mode: ParseMode) -> Result<FrameHeader, DecodingError> {
         let frame_header = if bytes.len() < 4 {
             return Err(DecodingError::UnexpectedEndOfStream);
         } else {
@@ -76,9 +246,13 @@ impl FrameHeader {
             return Err(DecodingError::InvalidFrameHeader);
         }
 
-        if b != 0b11 {
-            return Err(DecodingError::UnsupportedVersion);
-        }
+        let version = match b {
+            0b00 => Version::MPEG2_5,
+            0b01 => return Err(DecodingError::UnsupportedVersion),
+            0b10 => Version::MPEG2,
+            0b11 => Version::MPEG1,
+            _ => unreachable!(),
+        };
 
         if c != 0b01 {
             return Err(DecodingError::UnsupportedLayer);
@@ -90,40 +264,26 @@ impl FrameHeader {
             Some(read_u16(&mut bytes)?)
         };
 
-        // for MPEG-1, Layer III
-        let bitrate = match e {
-            0b0000 => return Err(DecodingError::UnsupportedBitrate),
-            0b0001 => 32,
-            0b0010 => 40,
-            0b0011 => 48,
-            0b0100 => 56,
-            0b0101 => 64,
-            0b0110 => 80,
-            0b0111 => 96,
-            0b1000 => 112,
-            0b1001 => 128,
-            0b1010 => 160,
-            0b1011 => 192,
-            0b1100 => 224,
-            0b1101 => 256,
-            0b1110 => 320,
-            0b1111 => return Err(DecodingError::UnsupportedBitrate),
-            _ => unreachable!(),
+        let bitrate = match bitrates(version)[e as usize] {
+            0 => return Err(DecodingError::UnsupportedBitrate),
+            bitrate => bitrate,
         };
 
-        // for MPEG-1, Layer III
-        let sampling_rate = match f {
-            0b00 => 44100,
-            0b01 => 48000,
-            0b10 => 32000,
-            0b11 => return Err(DecodingError::UnsupportedSamplingRate),
-            _ => unreachable!(),
+        let sampling_rate = match SAMPLING_RATES.get(f as usize) {
+            Some(sampling_rate) => *sampling_rate,
+            None => return Err(DecodingError::UnsupportedSamplingRate),
+        };
+        let sampling_rate = match version {
+            Version::MPEG1 => sampling_rate,
+            Version::MPEG2 => sampling_rate / 2,
+            Version::MPEG2_5 => sampling_rate / 4,
         };
 
         let padding = g;
 
-        // For Layer III
-        let frame_bytes = 144 * bitrate as u32 * 1000 / sampling_rate + padding;
+        // For Layer III; the LSF versions carry one granule per frame instead of two
+        let slot_factor = if version == Version::MPEG1 { 144 } else { 72 };
+        let frame_bytes = slot_factor * bitrate * 1000 / sampling_rate + padding;
 
         let private_bit = h == 1;
 
@@ -135,14 +295,17 @@ impl FrameHeader {
             _ => unreachable!(),
         };
 
+        // For Layer III, the mode extension toggles the two stereo coding tools
         let (intensity_stereo, ms_stereo) = if channel_mode == ChannelMode::JointStereo {
             match j {
-                0b00 => (true, false),
-                0b01 => (false, true),
-                0b10 => (false, false),
-                0b11 => (false, false),
+                0b00 => (false, false),
+                0b01 => (true, false),
+                0b10 => (false, true),
+                0b11 => (true, true),
                 _ => unreachable!(),
             }
+        } else if mode == ParseMode::Strict && j != 0 {
+            return Err(DecodingError::InvalidFrameHeader);
         } else {
             (false, false)
         };
@@ -154,17 +317,20 @@ impl FrameHeader {
         let emphasis = match m {
             0b00 => Emphasis::None,
             0b01 => Emphasis::FiftyFifteenMs,
-            0b10 => return Err(DecodingError::UnsupportedEmphasis),
+            0b10 if mode == ParseMode::Strict => return Err(DecodingError::UnsupportedEmphasis),
+            0b10 => Emphasis::Reserved,
             0b11 => Emphasis::CCITTJ17,
             _ => unreachable!(),
         };
 
         Ok(FrameHeader {
-            version: Version::MPEG1,
+            raw: frame_header,
+            version,
             layer: Layer::LayerIII,
             bitrate,
             sampling_rate,
             frame_bytes: frame_bytes as usize,
+            padding: padding == 1,
             crc,
             private_bit,
             channel_mode,
@@ -177,6 +343,41 @@ impl FrameHeader {
     }
 }
 
+/// A one-line summary, e.g. "MPEG-1 Layer III, 128 kbps, 44100 Hz, Joint
+/// Stereo (MS), no CRC".
+impl fmt::Display for FrameHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = match self.version {
+            Version::MPEG1 => "MPEG-1",
+            Version::MPEG2 => "MPEG-2",
+            Version::MPEG2_5 => "MPEG-2.5",
+        };
+        let layer = match self.layer {
+            Layer::LayerI => "Layer I",
+            Layer::LayerII => "Layer II",
+            Layer::LayerIII => "Layer III",
+        };
+        let channel_mode = match self.channel_mode {
+            ChannelMode::Stereo => "Stereo",
+            ChannelMode::JointStereo => match (self.ms_stereo, self.intensity_stereo) {
+                (true, true) => "Joint Stereo (MS+IS)",
+                (true, false) => "Joint Stereo (MS)",
+                (false, true) => "Joint Stereo (IS)",
+                (false, false) => "Joint Stereo",
+            },
+            ChannelMode::DualChannel => "Dual Channel",
+            ChannelMode::Mono => "Mono",
+        };
+        write!(
+            f,
+            "{version} {layer}, {} kbps, {} Hz, {channel_mode}, {}",
+            self.bitrate,
+            self.sampling_rate,
+            if self.crc.is_some() { "CRC" } else { "no CRC" }
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -187,12 +388,14 @@ mod tests {
         assert_eq!(
             result,
             FrameHeader {
+                raw: 0xFFFB90C4,
                 version: Version::MPEG1,
                 layer: Layer::LayerIII,
                 bitrate: 128,
                 sampling_rate: 44100,
                 crc: None,
                 frame_bytes: 417,
+                padding: false,
                 private_bit: false,
                 channel_mode: ChannelMode::Mono,
                 intensity_stereo: false,
@@ -207,12 +410,14 @@ mod tests {
         assert_eq!(
             result,
             FrameHeader {
+                raw: 0xFFFBC0C4,
                 version: Version::MPEG1,
                 layer: Layer::LayerIII,
                 bitrate: 224,
                 sampling_rate: 44100,
                 crc: None,
                 frame_bytes: 731,
+                padding: false,
                 private_bit: false,
                 channel_mode: ChannelMode::Mono,
                 intensity_stereo: false,
@@ -227,12 +432,14 @@ mod tests {
         assert_eq!(
             result,
             FrameHeader {
+                raw: 0xFFFB30C4,
                 version: Version::MPEG1,
                 layer: Layer::LayerIII,
                 bitrate: 48,
                 sampling_rate: 44100,
                 crc: None,
                 frame_bytes: 156,
+                padding: false,
                 private_bit: false,
                 channel_mode: ChannelMode::Mono,
                 intensity_stereo: false,
@@ -247,12 +454,14 @@ mod tests {
         assert_eq!(
             result,
             FrameHeader {
+                raw: 0xFFFB20C4,
                 version: Version::MPEG1,
                 layer: Layer::LayerIII,
                 bitrate: 40,
                 sampling_rate: 44100,
                 crc: None,
                 frame_bytes: 130,
+                padding: false,
                 private_bit: false,
                 channel_mode: ChannelMode::Mono,
                 intensity_stereo: false,
@@ -267,12 +476,14 @@ mod tests {
         assert_eq!(
             result,
             FrameHeader {
+                raw: 0xFFFB10C4,
                 version: Version::MPEG1,
                 layer: Layer::LayerIII,
                 bitrate: 32,
                 sampling_rate: 44100,
                 crc: None,
                 frame_bytes: 104,
+                padding: false,
                 private_bit: false,
                 channel_mode: ChannelMode::Mono,
                 intensity_stereo: false,
@@ -283,4 +494,216 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_reading_lsf_frame_header() {
+        let result = FrameHeader::read(b"\xFF\xF3\x80\xC4").unwrap();
+        assert_eq!(
+            result,
+            FrameHeader {
+                raw: 0xFFF380C4,
+                version: Version::MPEG2,
+                layer: Layer::LayerIII,
+                bitrate: 64,
+                sampling_rate: 22050,
+                crc: None,
+                frame_bytes: 208,
+                padding: false,
+                private_bit: false,
+                channel_mode: ChannelMode::Mono,
+                intensity_stereo: false,
+                ms_stereo: false,
+                copyright: false,
+                original: true,
+                emphasis: Emphasis::None,
+            }
+        );
+
+        let result = FrameHeader::read(b"\xFF\xE3\x12\x40").unwrap();
+        assert_eq!(result.version, Version::MPEG2_5);
+        assert_eq!(result.bitrate, 8);
+        assert_eq!(result.sampling_rate, 11025);
+        assert_eq!(result.frame_bytes, 53);
+        assert_eq!(result.channel_mode, ChannelMode::JointStereo);
+
+        assert_eq!(
+            FrameHeader::read(b"\xFF\xEB\x90\xC4"),
+            Err(DecodingError::UnsupportedVersion)
+        );
+    }
+
+    #[test]
+    fn test_plausible_sync() {
+        assert!(FrameHeader::is_plausible_sync(b"\xFF\xFB\x90\xC4"));
+        assert!(FrameHeader::is_plausible_sync(b"\xFF\xF3\x80\xC4"));
+        assert!(!FrameHeader::is_plausible_sync(b"\xFF\xFB\x00\xC4"));
+        assert!(!FrameHeader::is_plausible_sync(b"ID3\x04"));
+
+        // Agrees with `read` across the whole second half of the header word
+        for low in 0..=0x1F_FFFF_u32 {
+            let word = 0xFFE0_0000 | low;
+            let bytes = word.to_be_bytes();
+            let header = FrameHeader::read(&[bytes[0], bytes[1], bytes[2], bytes[3], 0, 0]);
+            assert_eq!(FrameHeader::is_plausible_sync(&bytes), header.is_ok());
+            if let Ok(header) = header {
+                assert_eq!(header.raw(), word);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reading_all_header_fields() {
+        let result = FrameHeader::read(b"\xFF\xFA\x92\x6D\xAB\xCD").unwrap();
+        assert_eq!(
+            result,
+            FrameHeader {
+                raw: 0xFFFA926D,
+                version: Version::MPEG1,
+                layer: Layer::LayerIII,
+                bitrate: 128,
+                sampling_rate: 44100,
+                crc: Some(0xABCD),
+                frame_bytes: 418,
+                padding: true,
+                private_bit: false,
+                channel_mode: ChannelMode::JointStereo,
+                intensity_stereo: false,
+                ms_stereo: true,
+                copyright: true,
+                original: true,
+                emphasis: Emphasis::FiftyFifteenMs,
+            }
+        );
+        assert_eq!(result.len(), 6);
+    }
+
+    #[test]
+    fn test_mode_extension() {
+        // Bit 0 of the mode extension is intensity stereo and bit 1 is M/S
+        // stereo, as in ISO/IEC 11172-3 2.4.2.3
+        let mode_extensions = [
+            (0x40, false, false),
+            (0x50, true, false),
+            (0x60, false, true),
+            (0x70, true, true),
+        ];
+        for (byte, intensity_stereo, ms_stereo) in mode_extensions {
+            let result = FrameHeader::read(&[0xFF, 0xFB, 0x91, byte]).unwrap();
+            assert_eq!(result.channel_mode, ChannelMode::JointStereo);
+            assert!(result.private_bit);
+            assert_eq!(result.intensity_stereo, intensity_stereo);
+            assert_eq!(result.ms_stereo, ms_stereo);
+        }
+
+        // The mode extension is ignored outside of joint stereo
+        let result = FrameHeader::read(b"\xFF\xFB\x90\x30").unwrap();
+        assert_eq!(result.channel_mode, ChannelMode::Stereo);
+        assert!(!result.intensity_stereo && !result.ms_stereo);
+    }
+
+    #[test]
+    fn test_write() {
+        // Reverses `read` across the whole second half of the header word,
+        // except for the mode extension outside of joint stereo
+        for low in 0..=0x1F_FFFF_u32 {
+            let word = 0xFFE0_0000 | low;
+            let bytes = word.to_be_bytes();
+            if let Ok(header) = FrameHeader::read(&[bytes[0], bytes[1], bytes[2], bytes[3], 0, 0]) {
+                let expected = if header.channel_mode == ChannelMode::JointStereo {
+                    word
+                } else {
+                    word & !0x30
+                };
+                assert_eq!(header.write(), expected.to_be_bytes());
+            }
+        }
+
+        let header = FrameHeader::read(b"\xFF\xFA\x92\x6D\xAB\xCD").unwrap();
+        assert_eq!(header.write_with_crc(), b"\xFF\xFA\x92\x6D\xAB\xCD");
+
+        // Edited fields
+        let edited = FrameHeader {
+            bitrate: 320,
+            padding: false,
+            channel_mode: ChannelMode::Mono,
+            crc: None,
+            ..header
+        };
+        let header = FrameHeader::read(&edited.write()).unwrap();
+        assert_eq!(header.bitrate, 320);
+        assert_eq!(header.frame_bytes, 1044);
+        assert_eq!(header.channel_mode, ChannelMode::Mono);
+        assert!(!header.ms_stereo);
+        assert_eq!(header.emphasis, Emphasis::FiftyFifteenMs);
+        assert_eq!(header.len(), 4);
+
+        // An unknown bitrate keeps the bits it was read with
+        let header = FrameHeader {
+            bitrate: 100,
+            ..header
+        };
+        assert_eq!(FrameHeader::read(&header.write()).unwrap().bitrate, 320);
+    }
+
+    #[test]
+    fn test_compute_crc() {
+        let mut header = FrameHeader::read(b"\xFF\xFA\x90\xC4\x00\x00").unwrap();
+        let side_info = [0x12; 17];
+        header.crc = Some(header.compute_crc(&side_info));
+        let mut frame = header.write_with_crc();
+        frame.extend_from_slice(&side_info);
+        frame.resize(header.frame_bytes, 0);
+        assert!(crate::crc::crc_matches(&header, &frame));
+    }
+
+    #[test]
+    fn test_samples_per_frame() {
+        let header = FrameHeader::read(b"\xFF\xFB\x90\xC4").unwrap();
+        assert_eq!(header.samples_per_frame(), 1152);
+        assert_eq!(header.duration(), Duration::from_secs(1152) / 44100);
+
+        // MPEG-2 at 22.05 kHz
+        let header = FrameHeader::read(b"\xFF\xF3\x90\xC4").unwrap();
+        assert_eq!(header.samples_per_frame(), 576);
+        assert_eq!(header.duration(), Duration::from_secs(576) / 22050);
+    }
+
+    #[test]
+    fn test_display() {
+        let header = FrameHeader::read(b"\xFF\xFB\x90\x64").unwrap();
+        assert_eq!(
+            header.to_string(),
+            "MPEG-1 Layer III, 128 kbps, 44100 Hz, Joint Stereo (MS), no CRC"
+        );
+        let header = FrameHeader::read(b"\xFF\xF2\x90\xC4\x00\x00").unwrap();
+        assert_eq!(
+            header.to_string(),
+            "MPEG-2 Layer III, 80 kbps, 22050 Hz, Mono, CRC"
+        );
+    }
+
+    #[test]
+    fn test_parse_modes() {
+        let reserved_emphasis = b"\xFF\xFB\x90\xC6";
+        assert_eq!(
+            FrameHeader::read(reserved_emphasis).unwrap().emphasis,
+            Emphasis::Reserved
+        );
+        assert_eq!(
+            FrameHeader::read_with_mode(reserved_emphasis, ParseMode::Strict),
+            Err(DecodingError::UnsupportedEmphasis)
+        );
+
+        let stereo_mode_extension = b"\xFF\xFB\x90\x30";
+        assert!(FrameHeader::read(stereo_mode_extension).is_ok());
+        assert_eq!(
+            FrameHeader::read_with_mode(stereo_mode_extension, ParseMode::Strict),
+            Err(DecodingError::InvalidFrameHeader)
+        );
+
+        assert_eq!(
+            FrameHeader::read_with_mode(b"\xFF\xFB\x90\xC4", ParseMode::Strict),
+            FrameHeader::read(b"\xFF\xFB\x90\xC4")
+        );
+    }
 }
@@ -0,0 +1,168 @@
+use std::io::{self, Read};
+
+/// Strips the metadata blocks that SHOUTcast/Icecast servers interleave into
+/// the audio when requested with `Icy-MetaData: 1`, leaving plain MP3 data.
+/// `metaint` is the `icy-metaint` value from the response headers.
+#[derive(Debug)]
+pub struct IcyReader<R> {
+    inner: R,
+    metaint: usize,
+    /// Audio bytes left before the next metadata block.
+    until_metadata: usize,
+    metadata: Vec<u8>,
+    stream_title: Option<String>,
+}
+
+impl<R: Read> IcyReader<R> {
+    pub fn new(inner: R, metaint: usize) -> Self {
+        IcyReader {
+            inner,
+            metaint,
+            until_metadata: metaint,
+            metadata: Vec::new(),
+            stream_title: None,
+        }
+    }
+
+    /// The contents of the last non-empty metadata block, e.g.
+    /// `StreamTitle='Artist - Title';StreamUrl='';`, without padding.
+    pub fn metadata(&self) -> &[u8] {
+        &self.metadata
+    }
+
+    /// The `StreamTitle` of the last metadata block that carried one.
+    pub fn stream_title(&self) -> Option<&str> {
+        self.stream_title.as_deref()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads the metadata block at the current position. Returns `false` if
+    /// the stream ended instead.
+    fn read_metadata(&mut self) -> io::Result<bool> {
+        let mut len = [0];
+        if self.inner.read(&mut len)? == 0 {
+            return Ok(false);
+        }
+        // An empty block means the metadata didn't change
+        if len[0] > 0 {
+            self.metadata.resize(len[0] as usize * 16, 0);
+            self.inner.read_exact(&mut self.metadata)?;
+            let end = self
+                .metadata
+                .iter()
+                .rposition(|&b| b != 0)
+                .map_or(0, |i| i + 1);
+            self.metadata.truncate(end);
+            if let Some(title) = field(&self.metadata, b"StreamTitle") {
+                self.stream_title = Some(String::from_utf8_lossy(title).into_owned());
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// The value of `key='value';` in `metadata`. The value may itself contain
+/// quotes, so it ends at the first `';`.
+fn field<'a>(metadata: &'a [u8], key: &[u8]) -> Option<&'a [u8]> {
+    let start = metadata
+        .windows(key.len() + 2)
+        .position(|window| window.starts_with(key) && window.ends_with(b"='"))?
+        + key.len()
+        + 2;
+    let value = &metadata[start..];
+    let len = value
+        .windows(2)
+        .position(|window| window == b"';")
+        .unwrap_or(value.len().saturating_sub(1));
+    Some(&value[..len])
+}
+
+impl<R: Read> Read for IcyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.until_metadata == 0 {
+            if !self.read_metadata()? {
+                return Ok(0);
+            }
+            self.until_metadata = self.metaint;
+        }
+
+        let len = buf.len().min(self.until_metadata);
+        let read = self.inner.read(&mut buf[..len])?;
+        self.until_metadata -= read;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::StreamParser;
+
+    use super::*;
+    use std::fs::read;
+
+    fn metadata_block(text: &[u8]) -> Vec<u8> {
+        let len = text.len().div_ceil(16);
+        let mut block = vec![len as u8];
+        block.extend_from_slice(text);
+        block.resize(1 + len * 16, 0);
+        block
+    }
+
+    #[test]
+    fn test_icy_reader() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let metaint = 1000;
+        let mut stream = Vec::new();
+        for (i, chunk) in data.chunks(metaint).enumerate() {
+            stream.extend_from_slice(chunk);
+            if chunk.len() < metaint {
+                break;
+            }
+            match i {
+                0 => stream.extend(metadata_block(b"StreamTitle='It's a Test';StreamUrl='';")),
+                1 => stream.push(0),
+                _ => stream.extend(metadata_block(b"StreamTitle='Next';")),
+            }
+        }
+
+        let mut reader = IcyReader::new(&stream[..], metaint);
+        let mut parser = StreamParser::new();
+        let mut audio = Vec::new();
+        let mut buf = [0; 300];
+        let mut frames = 0;
+        loop {
+            let read = reader.read(&mut buf).unwrap();
+            if read == 0 {
+                break;
+            }
+            if audio.len() >= 1000 && audio.len() < 2000 {
+                assert_eq!(reader.stream_title(), Some("It's a Test"));
+            }
+            audio.extend_from_slice(&buf[..read]);
+            parser.feed(&buf[..read]);
+            while let Some(frame) = parser.next_frame() {
+                frame.unwrap();
+                frames += 1;
+            }
+        }
+
+        assert_eq!(audio, data);
+        assert_eq!(frames, 21);
+        assert_eq!(reader.stream_title(), Some("Next"));
+        assert_eq!(reader.metadata(), b"StreamTitle='Next';");
+    }
+
+    #[test]
+    fn test_icy_field() {
+        assert_eq!(field(b"StreamTitle='';", b"StreamTitle"), Some(&b""[..]));
+        assert_eq!(field(b"StreamUrl='x';", b"StreamTitle"), None);
+        // Unterminated values run to the end, minus the closing quote
+        assert_eq!(field(b"StreamTitle='A'", b"StreamTitle"), Some(&b"A"[..]));
+    }
+}
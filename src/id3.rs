@@ -0,0 +1,309 @@
+use std::time::Duration;
+
+use crate::lame::ReplayGain;
+
+/// Reads a "syncsafe" integer, which has 7 bits per byte.
+fn syncsafe(bytes: &[u8]) -> Option<usize> {
+    if bytes.iter().any(|byte| byte & 0x80 != 0) {
+        return None;
+    }
+    Some(
+        bytes
+            .iter()
+            .fold(0_usize, |acc, &byte| acc << 7 | byte as usize),
+    )
+}
+
+fn write_syncsafe(value: usize) -> [u8; 4] {
+    [21, 14, 7, 0].map(|shift| (value >> shift) as u8 & 0x7F)
+}
+
+/// Length of the ID3v2 tag at the start of `data`, including its header and
+/// footer, or `None` if `data` doesn't start with a tag.
+pub fn id3v2_len(data: &[u8]) -> Option<usize> {
+    let header: &[u8; 10] = data.get(..10)?.try_into().unwrap();
+    if &header[..3] != b"ID3" || header[3] == 0xFF || header[4] == 0xFF {
+        return None;
+    }
+
+    let flags = header[5];
+    let size = syncsafe(&header[6..10])?;
+    let footer = if flags & 0x10 != 0 { 10 } else { 0 };
+    Some(10 + size + footer)
+}
+
+/// Length of all the complete ID3v2 tags at the start of `data`. Some files
+/// have more than one.
+pub(crate) fn leading_id3v2_len(data: &[u8]) -> usize {
+    let mut start = 0;
+    while let Some(len) = id3v2_len(&data[start..]) {
+        if start + len > data.len() {
+            break;
+        }
+        start += len;
+    }
+    start
+}
+
+/// Undoes the unsynchronisation scheme, which inserts a zero byte after each
+/// 0xFF so that the tag contains no false frame syncs.
+fn resynchronise(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len());
+    for (index, &byte) in data.iter().enumerate() {
+        if !(byte == 0 && index > 0 && data[index - 1] == 0xFF) {
+            output.push(byte);
+        }
+    }
+    output
+}
+
+/// Decodes the contents of a text frame. Of several values, as ID3v2.4
+/// separates with zero bytes, only the first is kept.
+fn read_text(data: &[u8]) -> Option<String> {
+    let (&encoding, text) = data.split_first()?;
+    let utf16 = |text: &[u8], big_endian: bool| {
+        let units: Vec<_> = text
+            .chunks_exact(2)
+            .map(|pair| {
+                let pair = [pair[0], pair[1]];
+                if big_endian {
+                    u16::from_be_bytes(pair)
+                } else {
+                    u16::from_le_bytes(pair)
+                }
+            })
+            .take_while(|&unit| unit != 0)
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    let text = match encoding {
+        // Latin-1
+        0 => text
+            .iter()
+            .take_while(|&&byte| byte != 0)
+            .map(|&byte| byte as char)
+            .collect(),
+        // UTF-16 with a byte order mark
+        1 => match text {
+            [0xFF, 0xFE, rest @ ..] => utf16(rest, false),
+            [0xFE, 0xFF, rest @ ..] => utf16(rest, true),
+            _ => utf16(text, true),
+        },
+        2 => utf16(text, true),
+        3 => {
+            let end = text
+                .iter()
+                .position(|&byte| byte == 0)
+                .unwrap_or(text.len());
+            String::from_utf8_lossy(&text[..end]).into_owned()
+        }
+        _ => return None,
+    };
+    Some(text)
+}
+
+/// Title, artist and the like, as stored in ID3v2 text frames. See
+/// `read_metadata` for all the tags of a file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// The recording time, usually just the year.
+    pub year: Option<String>,
+    /// The track number, possibly followed by a slash and the track count.
+    pub track: Option<String>,
+    pub genre: Option<String>,
+    /// Playback time, which only `read_metadata` sets.
+    pub duration: Option<Duration>,
+    /// Which only `read_metadata` sets.
+    pub replay_gain: Option<ReplayGain>,
+}
+
+impl Metadata {
+    /// The field a text frame is stored in, with the IDs of ID3v2.2, 2.3 and
+    /// 2.4.
+    fn field(&mut self, id: &[u8]) -> Option<&mut Option<String>> {
+        Some(match id {
+            b"TIT2" | b"TT2" => &mut self.title,
+            b"TPE1" | b"TP1" => &mut self.artist,
+            b"TALB" | b"TAL" => &mut self.album,
+            b"TDRC" | b"TYER" | b"TYE" => &mut self.year,
+            b"TRCK" | b"TRK" => &mut self.track,
+            b"TCON" | b"TCO" => &mut self.genre,
+            _ => return None,
+        })
+    }
+
+    /// Sets the fields that are `None` to those of `other`.
+    pub(crate) fn fill_from(&mut self, other: Metadata) {
+        let fields = [
+            (&mut self.title, other.title),
+            (&mut self.artist, other.artist),
+            (&mut self.album, other.album),
+            (&mut self.year, other.year),
+            (&mut self.track, other.track),
+            (&mut self.genre, other.genre),
+        ];
+        for (field, other) in fields {
+            if field.is_none() {
+                *field = other;
+            }
+        }
+        self.duration = self.duration.or(other.duration);
+        self.replay_gain = self.replay_gain.or(other.replay_gain);
+    }
+
+    /// Reads the ID3v2.2, 2.3 or 2.4 tag at the start of `data`. Frames other
+    /// than the text frames of the fields, and compressed or encrypted ones,
+    /// are ignored. Returns `None` if `data` doesn't start with a complete
+    /// tag.
+    pub fn from_id3v2(data: &[u8]) -> Option<Self> {
+        let len = id3v2_len(data)?;
+        let (version, flags) = (data[3], data[5]);
+        let footer = if flags & 0x10 != 0 { 10 } else { 0 };
+        let mut body = data.get(10..len - footer)?.to_vec();
+        // ID3v2.4 unsynchronises each frame instead
+        if flags & 0x80 != 0 && version < 4 {
+            body = resynchronise(&body);
+        }
+
+        let mut rest = &body[..];
+        if flags & 0x40 != 0 && version >= 3 {
+            // The extended header counts itself in ID3v2.4 but not in 2.3
+            let size = match version {
+                3 => 4 + u32::from_be_bytes(rest.get(..4)?.try_into().unwrap()) as usize,
+                _ => syncsafe(rest.get(..4)?)?,
+            };
+            rest = rest.get(size..)?;
+        }
+
+        let mut metadata = Metadata::default();
+        let (id_len, header_len) = if version == 2 { (3, 6) } else { (4, 10) };
+        // Frames end where the padding starts
+        while rest.len() >= header_len && rest[0] != 0 {
+            let (id, size_bytes) = (&rest[..id_len], &rest[id_len..2 * id_len]);
+            let size = match version {
+                2 => size_bytes
+                    .iter()
+                    .fold(0, |acc, &byte| acc << 8 | byte as usize),
+                3 => u32::from_be_bytes(size_bytes.try_into().unwrap()) as usize,
+                _ => syncsafe(size_bytes)?,
+            };
+            let frame_flags = if version == 2 { 0 } else { rest[9] };
+            let mut contents = rest.get(header_len..header_len + size)?.to_vec();
+            rest = &rest[header_len + size..];
+
+            let (skipped, extra) = match version {
+                2 => (false, 0),
+                // Compression or encryption, and grouping
+                3 => (frame_flags & 0xC0 != 0, (frame_flags & 0x20 != 0) as usize),
+                // Compression or encryption, and grouping and data length
+                _ => (
+                    frame_flags & 0x0C != 0,
+                    (frame_flags & 0x40 != 0) as usize + (frame_flags & 0x01 != 0) as usize * 4,
+                ),
+            };
+            if skipped || extra > contents.len() {
+                continue;
+            }
+            contents.drain(..extra);
+            if version >= 4 && (flags & 0x80 != 0 || frame_flags & 0x02 != 0) {
+                contents = resynchronise(&contents);
+            }
+            if let Some(field) = metadata.field(id) {
+                *field = read_text(&contents);
+            }
+        }
+        Some(metadata)
+    }
+
+    /// Writes the fields that are set as an ID3v2.4 tag of UTF-8 text frames.
+    pub fn to_id3v2(&self) -> Vec<u8> {
+        let fields = [
+            (b"TIT2", &self.title),
+            (b"TPE1", &self.artist),
+            (b"TALB", &self.album),
+            (b"TDRC", &self.year),
+            (b"TRCK", &self.track),
+            (b"TCON", &self.genre),
+        ];
+        let mut frames = Vec::new();
+        for (id, value) in fields {
+            let Some(value) = value else { continue };
+            frames.extend_from_slice(id);
+            frames.extend_from_slice(&write_syncsafe(1 + value.len()));
+            // No flags, and UTF-8
+            frames.extend_from_slice(&[0, 0, 3]);
+            frames.extend_from_slice(value.as_bytes());
+        }
+
+        let mut tag = b"ID3\x04\x00\x00".to_vec();
+        tag.extend_from_slice(&write_syncsafe(frames.len()));
+        tag.extend_from_slice(&frames);
+        tag
+    }
+
+    /// A copy of the file in `data` that starts with the ID3v2 tag of this
+    /// metadata instead of any ID3v2 tags it had.
+    pub fn prepend_to(&self, data: &[u8]) -> Vec<u8> {
+        let mut output = self.to_id3v2();
+        output.extend_from_slice(&data[leading_id3v2_len(data)..]);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_id3v2_len() {
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        assert_eq!(id3v2_len(&data), Some(32));
+
+        assert_eq!(
+            id3v2_len(b"ID3\x04\x00\x00\x00\x00\x02\x01"),
+            Some(10 + 257)
+        );
+        assert_eq!(id3v2_len(b"ID3\x04\x00\x10\x00\x00\x00\x00"), Some(20));
+        assert_eq!(id3v2_len(b"ID3\x04\x00\x00\x00\x00\x80\x00"), None);
+        assert_eq!(id3v2_len(b"ID3\x04\x00\x00"), None);
+        assert_eq!(id3v2_len(b"\xFF\xFB\x90\xC4\x00\x00\x00\x00\x00\x00"), None);
+    }
+
+    #[test]
+    fn test_metadata() {
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        // Only a TSSE frame
+        assert_eq!(Metadata::from_id3v2(&data), Some(Metadata::default()));
+
+        let metadata = Metadata {
+            title: Some("Sine wave".to_string()),
+            artist: Some("Ünknown".to_string()),
+            track: Some("1/2".to_string()),
+            ..Metadata::default()
+        };
+        let tagged = metadata.prepend_to(&data);
+        assert_eq!(Metadata::from_id3v2(&tagged), Some(metadata.clone()));
+        assert_eq!(tagged[id3v2_len(&tagged).unwrap()..], data[32..]);
+        // Replacing the tag again
+        assert_eq!(metadata.prepend_to(&tagged), tagged);
+        assert_eq!(Metadata::default().to_id3v2(), b"ID3\x04\0\0\0\0\0\0");
+
+        // ID3v2.3 with UTF-16 and Latin-1, and unsynchronisation
+        let mut tag = b"ID3\x03\x00\x80\x00\x00\x00\x24".to_vec();
+        tag.extend_from_slice(b"TIT2\0\0\0\x07\0\0\x01\xFF\x00\xFEH\0i\0");
+        tag.extend_from_slice(b"TALB\0\0\0\x03\0\0\0\xE4\0");
+        tag.resize(10 + 0x24, 0);
+        let metadata = Metadata::from_id3v2(&tag).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Hi"));
+        assert_eq!(metadata.album.as_deref(), Some("ä"));
+
+        // ID3v2.2
+        let tag = b"ID3\x02\x00\x00\x00\x00\x00\x0BTP1\0\0\x05\0Sine";
+        let metadata = Metadata::from_id3v2(tag).unwrap();
+        assert_eq!(metadata.artist.as_deref(), Some("Sine"));
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{DecodingError, read_u16, read_u32};
+
+/// Samples of delay added by the decoder, which the encoder delay in the tag
+/// doesn't include.
+pub(crate) const DECODER_DELAY: u64 = 529;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReplayGain {
+    /// Peak signal amplitude, where 1.0 is digital full scale.
+    pub peak: Option<f32>,
+    /// Track ("radio") gain adjustment in dB.
+    pub track_gain: Option<f32>,
+    /// Album ("audiophile") gain adjustment in dB.
+    pub album_gain: Option<f32>,
+}
+
+impl ReplayGain {
+    fn read_peak(peak: u32) -> Option<f32> {
+        // Stored as fixed point with 23 fractional bits; zero means unknown.
+        if peak == 0 {
+            None
+        } else {
+            Some(peak as f32 / (1 << 23) as f32)
+        }
+    }
+
+    fn write_peak(peak: Option<f32>) -> u32 {
+        peak.map_or(0, |peak| (peak * (1 << 23) as f32).round() as u32)
+    }
+
+    fn write_gain(gain: Option<f32>, name: u16, originator: u8) -> u16 {
+        let Some(gain) = gain else {
+            return 0;
+        };
+        let value = ((gain.abs() * 10.0).round() as u16).min(0b1_1111_1111);
+        // Unset for a gain that is, so say 0b011: determined automatically
+        let originator = match originator & 0b111 {
+            0 => 0b011,
+            originator => originator as u16,
+        };
+        name << 13 | originator << 10 | ((gain < 0.0) as u16) << 9 | value
+    }
+
+    fn read_gain(gain: u16, expected_name: u16) -> Option<f32> {
+        let name = gain >> 13;
+        let negative = gain >> 9 & 0b1 == 1;
+        let value = (gain & 0b1_1111_1111) as f32 / 10.0;
+
+        if name != expected_name {
+            return None;
+        }
+
+        Some(if negative { -value } else { value })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LameTag {
+    pub encoder: [u8; 9],
+    pub revision: u8,
+    pub vbr_method: u8,
+    pub lowpass: u32,
+    pub replay_gain: ReplayGain,
+    /// Who set the track and album gains, 3 bits each as stored. Written as
+    /// "determined automatically" for a gain without one.
+    pub track_gain_originator: u8,
+    pub album_gain_originator: u8,
+    pub flags: u8,
+    pub ath_type: u8,
+    pub bitrate: u8,
+    pub encoder_delay: u16,
+    pub encoder_padding: u16,
+    pub misc: u8,
+    pub mp3_gain: i8,
+    pub preset: u16,
+    /// The surround info, and the 2 unused bits above it, that share a word
+    /// with `preset`, as stored.
+    pub surround: u8,
+    pub music_length: u32,
+    pub music_crc: u16,
+    pub tag_crc: u16,
+}
+
+impl LameTag {
+    pub const LEN: usize = 36;
+
+    fn is_lame_tag(data: &[u8]) -> bool {
+        matches!(data.get(..4), Some(b"LAME" | b"L3.9" | b"Lavf" | b"Lavc"))
+    }
+
+    fn read_tag(mut data: &[u8]) -> Result<Self, DecodingError> {
+        if data.len() < Self::LEN {
+            return Err(DecodingError::UnexpectedEndOfStream);
+        }
+
+        let encoder = data[..9].try_into().unwrap();
+        let revision = data[9] >> 4;
+        let vbr_method = data[9] & 0b1111;
+        let lowpass = data[10] as u32 * 100;
+        data = &data[11..];
+
+        let peak = read_u32(&mut data)?;
+        let track_gain = read_u16(&mut data)?;
+        let album_gain = read_u16(&mut data)?;
+        let replay_gain = ReplayGain {
+            peak: ReplayGain::read_peak(peak),
+            track_gain: ReplayGain::read_gain(track_gain, 1),
+            album_gain: ReplayGain::read_gain(album_gain, 2),
+        };
+
+        let track_gain_originator = (track_gain >> 10 & 0b111) as u8;
+        let album_gain_originator = (album_gain >> 10 & 0b111) as u8;
+
+        let flags = data[0] >> 4;
+        let ath_type = data[0] & 0b1111;
+        let bitrate = data[1];
+        let delay_padding = u32::from_be_bytes([0, data[2], data[3], data[4]]);
+        let encoder_delay = (delay_padding >> 12) as u16;
+        let encoder_padding = (delay_padding & 0xFFF) as u16;
+        let misc = data[5];
+        let mp3_gain = data[6] as i8;
+        data = &data[7..];
+
+        let preset = read_u16(&mut data)?;
+        let surround = (preset >> 11) as u8;
+        let preset = preset & 0b111_1111_1111;
+        let music_length = read_u32(&mut data)?;
+        let music_crc = read_u16(&mut data)?;
+        let tag_crc = read_u16(&mut data)?;
+
+        Ok(LameTag {
+            encoder,
+            revision,
+            vbr_method,
+            lowpass,
+            replay_gain,
+            track_gain_originator,
+            album_gain_originator,
+            flags,
+            ath_type,
+            bitrate,
+            encoder_delay,
+            encoder_padding,
+            misc,
+            mp3_gain,
+            preset,
+            surround,
+            music_length,
+            music_crc,
+            tag_crc,
+        })
+    }
+
+    /// Serializes the tag as `read` reads it. `music_length`, `music_crc` and
+    /// `tag_crc` depend on the file, and are written as they are.
+    pub fn write(&self) -> [u8; Self::LEN] {
+        let mut tag = [0; Self::LEN];
+        tag[..9].copy_from_slice(&self.encoder);
+        tag[9] = self.revision << 4 | self.vbr_method & 0b1111;
+        tag[10] = (self.lowpass / 100).min(255) as u8;
+        let replay_gain = &self.replay_gain;
+        tag[11..15].copy_from_slice(&ReplayGain::write_peak(replay_gain.peak).to_be_bytes());
+        let track_gain =
+            ReplayGain::write_gain(replay_gain.track_gain, 1, self.track_gain_originator);
+        tag[15..17].copy_from_slice(&track_gain.to_be_bytes());
+        let album_gain =
+            ReplayGain::write_gain(replay_gain.album_gain, 2, self.album_gain_originator);
+        tag[17..19].copy_from_slice(&album_gain.to_be_bytes());
+        tag[19] = self.flags << 4 | self.ath_type & 0b1111;
+        tag[20] = self.bitrate;
+        let delay_padding =
+            (self.encoder_delay as u32 & 0xFFF) << 12 | self.encoder_padding as u32 & 0xFFF;
+        tag[21..24].copy_from_slice(&delay_padding.to_be_bytes()[1..]);
+        tag[24] = self.misc;
+        tag[25] = self.mp3_gain as u8;
+        let preset = (self.surround as u16) << 11 | self.preset & 0b111_1111_1111;
+        tag[26..28].copy_from_slice(&preset.to_be_bytes());
+        tag[28..32].copy_from_slice(&self.music_length.to_be_bytes());
+        tag[32..34].copy_from_slice(&self.music_crc.to_be_bytes());
+        tag[34..36].copy_from_slice(&self.tag_crc.to_be_bytes());
+        tag
+    }
+
+    /// Reads the LAME extension that directly follows the Xing/Info fields.
+    pub fn read(data: &[u8]) -> Option<Result<Self, DecodingError>> {
+        if !Self::is_lame_tag(data) {
+            return None;
+        }
+        Some(Self::read_tag(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_real_lame_tag() {
+        let data = read("tests/sine_320hz_50ms_vbr_frame0.mp3").unwrap();
+        // header + side info + Xing fields
+        let lame = LameTag::read(&data[4 + 17 + 120..]).unwrap().unwrap();
+        assert_eq!(&lame.encoder, b"LAME3.100");
+        assert_eq!(lame.vbr_method, 4);
+        assert_eq!(lame.lowpass, 18500);
+        assert_eq!(lame.encoder_delay, 576);
+        assert_eq!(lame.encoder_padding, 675);
+        assert_eq!(lame.music_length, 1643);
+        assert_eq!(
+            lame.replay_gain,
+            ReplayGain {
+                peak: None,
+                track_gain: None,
+                album_gain: None,
+            }
+        );
+        assert_eq!(lame.write()[..], data[4 + 17 + 120..][..LameTag::LEN]);
+    }
+
+    #[test]
+    fn test_replay_gain() {
+        let mut tag = *b"LAME3.100\x04\xb9\x00\x80\x00\x00\x2a\x26\x44\x3b\x15\x20\x24\x02\xa3\x41\x00\xa9\xe0\x00\x00\x06\x6b\xd0\xc9\xa6\x82";
+        let lame = LameTag::read(&tag).unwrap().unwrap();
+        assert_eq!(
+            lame.replay_gain,
+            ReplayGain {
+                peak: Some(1.0),
+                // name: radio, originator: user, -3.8 dB
+                track_gain: Some(-3.8),
+                // name: audiophile, originator: artist, +5.9 dB
+                album_gain: Some(5.9),
+            }
+        );
+        assert_eq!(lame.track_gain_originator, 0b010);
+        assert_eq!(lame.album_gain_originator, 0b001);
+        assert_eq!(lame.preset, 480);
+        assert_eq!(lame.surround, 0b10_101);
+
+        // Written back as it was read
+        assert_eq!(lame.write(), tag);
+        let written = lame.write();
+        assert_eq!(LameTag::read(&written).unwrap().unwrap(), lame);
+
+        // A gain set without an originator was determined automatically
+        let lame = LameTag {
+            track_gain_originator: 0,
+            ..lame
+        };
+        assert_eq!(lame.write()[15..17], [0x2e, 0x26]);
+
+        // Name code zero means that the gain field is unset.
+        tag[15..17].copy_from_slice(&[0x0c, 0x26]);
+        let lame = LameTag::read(&tag).unwrap().unwrap();
+        assert_eq!(lame.replay_gain.track_gain, None);
+        assert_eq!(lame.write()[15..17], [0, 0]);
+    }
+
+    #[test]
+    fn test_not_lame_tag() {
+        assert!(LameTag::read(&[0; LameTag::LEN]).is_none());
+        assert_eq!(
+            LameTag::read(b"LAME3.100"),
+            Some(Err(DecodingError::UnexpectedEndOfStream))
+        );
+    }
+}
@@ -1,8 +1,7 @@
-use std::ops::{Range, Shl, Shr};
+use std::{error::Error, fmt, time::Duration};
 
-use header::FrameHeader;
-use side_info::SideInfo;
 use vbr::VbrInfo;
+use vbri::VbriInfo;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DecodingError {
@@ -14,64 +13,191 @@ pub enum DecodingError {
     UnsupportedSamplingRate,
     UnsupportedEmphasis,
     InvalidBlockType,
+    /// The CRC of the frame doesn't match its header and side info.
+    CrcMismatch,
+    /// The data ends partway through the last frame, as in a file that was
+    /// cut off. `available` bytes of the frame are present.
+    TruncatedFinalFrame {
+        available: usize,
+    },
 }
 
+impl DecodingError {
+    fn is_header_error(&self) -> bool {
+        matches!(
+            self,
+            DecodingError::InvalidFrameHeader
+                | DecodingError::UnsupportedVersion
+                | DecodingError::UnsupportedLayer
+                | DecodingError::UnsupportedBitrate
+                | DecodingError::UnsupportedSamplingRate
+                | DecodingError::UnsupportedEmphasis
+        )
+    }
+}
+
+impl fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            DecodingError::UnexpectedEndOfStream => "unexpected end of stream",
+            DecodingError::InvalidFrameHeader => "invalid frame header",
+            DecodingError::UnsupportedVersion => "unsupported MPEG version",
+            DecodingError::UnsupportedLayer => "unsupported MPEG layer",
+            DecodingError::UnsupportedBitrate => "unsupported bitrate",
+            DecodingError::UnsupportedSamplingRate => "unsupported sampling rate",
+            DecodingError::UnsupportedEmphasis => "unsupported emphasis",
+            DecodingError::InvalidBlockType => "invalid block type",
+            DecodingError::CrcMismatch => "CRC mismatch",
+            DecodingError::TruncatedFinalFrame { available } => {
+                return write!(f, "final frame truncated to {available} bytes");
+            }
+        };
+        f.write_str(message)
+    }
+}
+
+impl Error for DecodingError {}
+
+/// The structure that was being parsed when an error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Structure {
+    FrameHeader,
+    /// The frame as a whole, e.g. when it extends past the end of the data.
+    Frame,
+    SideInfo,
+    /// A Xing/Info header with its LAME tag, or a VBRI header.
+    VbrHeader,
+}
+
+/// A `DecodingError` together with where in the stream it occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamError {
+    pub error: DecodingError,
+    pub structure: Structure,
+    /// Byte offset of the start of the frame, relative to the data given to `FrameIter::new`.
+    pub offset: usize,
+    /// Index of the frame, counting the first frame as 0.
+    pub frame_index: usize,
+}
+
+impl fmt::Display for Structure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Structure::FrameHeader => "frame header",
+            Structure::Frame => "frame",
+            Structure::SideInfo => "side info",
+            Structure::VbrHeader => "VBR header",
+        })
+    }
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in {} of frame {} at byte {}",
+            self.error, self.structure, self.frame_index, self.offset
+        )
+    }
+}
+
+impl Error for StreamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+mod bits;
+mod crc;
+mod cut;
 mod decoder;
+mod encoder;
+#[cfg(feature = "ffi")]
+mod ffi;
 mod header;
+mod icy;
+mod id3;
+mod lame;
+mod probe;
+mod reader;
+mod reservoir;
+mod rev;
+mod ring;
+mod scan;
+mod seek_index;
 mod side_info;
+mod stream;
+mod tags;
+mod validate;
 mod vbr;
+mod vbri;
+mod wav;
+mod writer;
+
+pub use crc::CrcPolicy;
+pub use cut::{cut, join};
+pub use encoder::{BitrateMode, EncoderInfo, VbrHeader};
+pub use header::{ChannelMode, Emphasis, FrameHeader, Layer, ParseMode, Version};
+pub use icy::IcyReader;
+pub use id3::Metadata;
+pub use lame::{LameTag, ReplayGain};
+pub use probe::{Probe, probe};
+pub use reader::Reader;
+pub use reservoir::Reservoir;
+pub use rev::{RevFrameIter, find_previous_frame};
+pub use ring::{SampleConsumer, SampleProducer, sample_ring};
+pub use scan::{HeaderIter, ScanReport};
+pub use seek_index::{SeekIndex, SeekPoint};
+pub use side_info::{Block, BlockType, Channels, Granule, GranuleChannel, SideInfo};
+pub use stream::StreamParser;
+pub use tags::{audio_range, read_metadata, strip_tags};
+pub use validate::{Issue, ValidationReport, validate};
+pub use vbr::XingWriter;
+pub use wav::{WavSample, write_wav};
+pub use writer::FrameWriter;
 
 fn read_u16(data: &mut &[u8]) -> Result<u16, DecodingError> {
-    let int = u16::from_be_bytes(
-        data[..2]
-            .try_into()
-            .map_err(|_| DecodingError::UnexpectedEndOfStream)?,
-    );
-    *data = &data[2..];
-    Ok(int)
+    let (bytes, rest) = data
+        .split_first_chunk()
+        .ok_or(DecodingError::UnexpectedEndOfStream)?;
+    *data = rest;
+    Ok(u16::from_be_bytes(*bytes))
 }
 
 fn read_u32(data: &mut &[u8]) -> Result<u32, DecodingError> {
-    let int = u32::from_be_bytes(
-        data[..4]
-            .try_into()
-            .map_err(|_| DecodingError::UnexpectedEndOfStream)?,
-    );
-    *data = &data[4..];
-    Ok(int)
-}
-
-fn read_u64(data: &mut &[u8]) -> Result<u64, DecodingError> {
-    let int = u64::from_be_bytes(
-        data[..8]
-            .try_into()
-            .map_err(|_| DecodingError::UnexpectedEndOfStream)?,
-    );
-    *data = &data[8..];
-    Ok(int)
-}
-
-fn read_bits<T>(val: T, bits: Range<u8>) -> T
-where
-    T: Shl<u8, Output = T> + Shr<u8, Output = T>,
-{
-    (val << bits.start) >> (bits.start + size_of::<T>() as u8 * 8 - bits.end)
+    let (bytes, rest) = data
+        .split_first_chunk()
+        .ok_or(DecodingError::UnexpectedEndOfStream)?;
+    *data = rest;
+    Ok(u32::from_be_bytes(*bytes))
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum FirstFrame<'a> {
-    Vbr(FrameHeader, VbrInfo),
+    Vbr(FrameHeader, VbrInfo<'a>),
+    Vbri(FrameHeader, VbriInfo<'a>),
     Cbr(Frame<'a>),
 }
 
 impl FirstFrame<'_> {
+    pub fn header(&self) -> &FrameHeader {
+        match self {
+            FirstFrame::Vbr(header, _) | FirstFrame::Vbri(header, _) => header,
+            FirstFrame::Cbr(frame) => &frame.header,
+        }
+    }
+
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         match self {
             FirstFrame::Vbr(header, _) => header.frame_bytes,
+            FirstFrame::Vbri(header, _) => header.frame_bytes,
             FirstFrame::Cbr(frame) => frame.header.frame_bytes,
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Frame<'a> {
     pub header: header::FrameHeader,
     pub side_info: side_info::SideInfo,
@@ -79,18 +205,25 @@ pub struct Frame<'a> {
 }
 
 impl<'a> Frame<'a> {
-    fn read_header(data: &'a [u8]) -> Result<(FrameHeader, &'a [u8]), DecodingError> {
+    fn read_header_in(
+        data: &'a [u8],
+    ) -> Result<(FrameHeader, &'a [u8]), (Structure, DecodingError)> {
+        Self::read_header_with_mode(data, ParseMode::Lenient)
+    }
+
+    fn read_header_with_mode(
+        data: &'a [u8],
+        mode: ParseMode,
+    ) -> Result<(FrameHeader, &'a [u8]), (Structure, DecodingError)> {
         if data.len() < 4 {
-            return Err(DecodingError::UnexpectedEndOfStream);
+            return Err((Structure::FrameHeader, DecodingError::UnexpectedEndOfStream));
         }
 
-        let header = match FrameHeader::read(data) {
-            Ok(header) => header,
-            Err(err) => return Err(err),
-        };
+        let header =
+            FrameHeader::read_with_mode(data, mode).map_err(|err| (Structure::FrameHeader, err))?;
 
         if data.len() < header.frame_bytes {
-            return Err(DecodingError::UnexpectedEndOfStream);
+            return Err((Structure::Frame, DecodingError::UnexpectedEndOfStream));
         }
 
         let frame_data = &data[header.len()..header.frame_bytes];
@@ -113,51 +246,478 @@ impl<'a> Frame<'a> {
         })
     }
 
-    pub fn read_first(data: &'a [u8]) -> Result<FirstFrame<'a>, DecodingError> {
-        let (header, frame_data) = Frame::read_header(data)?;
+    fn read_first_in(data: &'a [u8]) -> Result<FirstFrame<'a>, (Structure, DecodingError)> {
+        let (header, frame_data) = Frame::read_header_in(data)?;
+        let in_vbr_header = |err| (Structure::VbrHeader, err);
 
-        if let Some(vbr_info) = VbrInfo::read(&header, &frame_data) {
-            Ok(FirstFrame::Vbr(header, vbr_info?))
+        if let Some(vbr_info) = VbrInfo::read(&header, frame_data) {
+            Ok(FirstFrame::Vbr(header, vbr_info.map_err(in_vbr_header)?))
+        } else if let Some(vbri_info) = VbriInfo::read(&header, frame_data) {
+            Ok(FirstFrame::Vbri(header, vbri_info.map_err(in_vbr_header)?))
         } else {
-            Ok(FirstFrame::Cbr(Self::read_frame_data(header, frame_data)?))
+            Self::read_frame_data(header, frame_data)
+                .map(FirstFrame::Cbr)
+                .map_err(|err| (Structure::SideInfo, err))
         }
     }
 
+    fn read_in(data: &'a [u8]) -> Result<Self, (Structure, DecodingError)> {
+        Self::read_in_with_mode(data, ParseMode::Lenient)
+    }
+
+    fn read_in_with_mode(
+        data: &'a [u8],
+        mode: ParseMode,
+    ) -> Result<Self, (Structure, DecodingError)> {
+        let (header, frame_data) = Frame::read_header_with_mode(data, mode)?;
+        Self::read_frame_data(header, frame_data).map_err(|err| (Structure::SideInfo, err))
+    }
+
+    pub fn read_first(data: &'a [u8]) -> Result<FirstFrame<'a>, DecodingError> {
+        Self::read_first_in(data).map_err(|(_, err)| err)
+    }
+
     pub fn read(data: &'a [u8]) -> Result<Self, DecodingError> {
-        let (header, frame_data) = Frame::read_header(data)?;
-        Self::read_frame_data(header, frame_data)
+        Self::read_in(data).map_err(|(_, err)| err)
+    }
+
+    /// Reads the first frame at or after `offset`, found as with
+    /// `FrameIter::starting_at`, and returns where it starts.
+    pub fn read_at(data: &'a [u8], offset: usize) -> Result<(usize, Self), DecodingError> {
+        let mut iter = FrameIter::starting_at(data, offset);
+        match iter.next() {
+            Some(Ok(frame)) => Ok((iter.byte_offset() - frame.header.frame_bytes, frame)),
+            Some(Err(err)) => Err(err.error),
+            None => Err(DecodingError::UnexpectedEndOfStream),
+        }
+    }
+
+    /// Amplitude below which `is_likely_silent` considers a frame silent:
+    /// the smallest step of 16-bit PCM, relative to full scale.
+    pub const SILENCE_THRESHOLD: f64 = 1.0 / 32768.0;
+
+    /// Whether the frame decodes to near silence, judged only from the global
+    /// gains and big values in this frame's side info, without decoding it,
+    /// as for finding the gaps in long recordings. The decoded output of a
+    /// silent frame may still be faintly audible at its start, as the IMDCT
+    /// overlap-adds it with the previous granule.
+    pub fn is_likely_silent(&self) -> bool {
+        // Mid/side stereo sums to up to sqrt(2) times either channel
+        let stereo_gain = if self.header.ms_stereo {
+            std::f64::consts::SQRT_2
+        } else {
+            1.0
+        };
+        self.side_info.amplitude_bound() * stereo_gain < Self::SILENCE_THRESHOLD
+    }
+
+    /// The granules of each channel in decoding order, granule 0 of all
+    /// channels first.
+    pub fn granules(&self) -> impl Iterator<Item = GranuleChannel<'_>> {
+        self.side_info.granule_channels(&self.header)
     }
 }
 
+/// A frame whose side info is only read when asked for, for indexing and
+/// scanning, which mostly need just the header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LazyFrame<'a> {
+    pub header: FrameHeader,
+    /// The frame after the header and CRC.
+    frame_data: &'a [u8],
+}
+
+impl<'a> LazyFrame<'a> {
+    pub fn read(data: &'a [u8]) -> Result<Self, DecodingError> {
+        let (header, frame_data) = Frame::read_header_in(data).map_err(|(_, err)| err)?;
+        Ok(LazyFrame { header, frame_data })
+    }
+
+    pub fn side_info(&self) -> Result<SideInfo, DecodingError> {
+        SideInfo::read(&self.header, self.frame_data)
+    }
+
+    pub fn main_data(&self) -> &'a [u8] {
+        self.frame_data
+            .get(SideInfo::len(&self.header)..)
+            .unwrap_or_default()
+    }
+
+    /// Reads the side info, making this a `Frame`.
+    pub fn frame(&self) -> Result<Frame<'a>, DecodingError> {
+        Frame::read_frame_data(self.header, self.frame_data)
+    }
+}
+
+/// Offset of the first plausible frame header in `data`.
+pub fn find_sync(data: &[u8]) -> Option<usize> {
+    data.windows(4)
+        .position(|bytes| FrameHeader::is_plausible_sync(bytes.try_into().unwrap()))
+}
+
+/// A position in the stream, see `FrameIter::resume`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// Byte offset from the start of the stream.
+    pub offset: usize,
+    /// Index of the frame at `offset`, counting the first frame as 0.
+    pub frame_index: usize,
+}
+
+#[derive(Debug, Clone)]
 pub struct FrameIter<'a> {
     data: &'a [u8],
+    offset: usize,
+    frame_index: usize,
+    resync: bool,
+    lossy: bool,
+    validate: bool,
+    /// One past the index of the last frame, if the first frame told the
+    /// number of frames.
+    end_index: Option<usize>,
+    mode: ParseMode,
+    crc_policy: CrcPolicy,
+    crc_mismatches: usize,
+    /// Whether the last frame was read right after the one before it, rather
+    /// than after skipping bytes.
+    in_sync: bool,
+    /// The header of the last frame that was read.
+    last_header: Option<FrameHeader>,
+    skipped_bytes: usize,
+    skipped_frames: usize,
 }
 
 impl<'a> FrameIter<'a> {
-    pub fn new(data: &'a [u8]) -> Result<(FirstFrame<'a>, Self), DecodingError> {
-        let first_frame = Frame::read_first(data)?;
+    pub fn new(data: &'a [u8]) -> Result<(FirstFrame<'a>, Self), StreamError> {
+        let first_frame = Frame::read_first_in(data).map_err(|(structure, error)| StreamError {
+            error,
+            structure,
+            offset: 0,
+            frame_index: 0,
+        })?;
         let consumed = first_frame.len();
-        Ok((
-            first_frame,
-            FrameIter {
-                data: &data[consumed..],
+        let position = Position {
+            offset: consumed,
+            frame_index: 1,
+        };
+        let frames = match &first_frame {
+            FirstFrame::Vbr(_, vbr_info) => vbr_info.frames(),
+            FirstFrame::Vbri(_, vbri_info) => Some(vbri_info.frames()),
+            FirstFrame::Cbr(_) => None,
+        };
+        let mut iter = Self::resume(&data[consumed..], position);
+        iter.last_header = Some(*first_frame.header());
+        iter.end_index = frames.map(|frames| frames as usize + 1);
+        Ok((first_frame, iter))
+    }
+
+    /// Continues iterating from `position`, such as one returned by `position`
+    /// on an earlier iterator, with `data` starting at that offset in the
+    /// stream. This lets a client that fetches a remote file in ranges parse
+    /// each range as it arrives. Offsets and frame indices carry on from
+    /// `position`.
+    pub fn resume(data: &'a [u8], position: Position) -> Self {
+        FrameIter {
+            data,
+            offset: position.offset,
+            frame_index: position.frame_index,
+            resync: false,
+            lossy: false,
+            validate: false,
+            end_index: None,
+            mode: ParseMode::Lenient,
+            crc_policy: CrcPolicy::Accept,
+            crc_mismatches: 0,
+            in_sync: true,
+            last_header: None,
+            skipped_bytes: 0,
+            skipped_frames: 0,
+        }
+    }
+
+    /// Iterates from an arbitrary byte position in `data`, e.g. one estimated
+    /// for seeking. The first frame is found as with `validate_next_header`,
+    /// and frame indices count from it. Offsets are relative to `data`.
+    pub fn starting_at(data: &'a [u8], offset: usize) -> Self {
+        let offset = offset.min(data.len());
+        let mut iter = Self::resume(
+            &data[offset..],
+            Position {
+                offset,
+                frame_index: 0,
             },
-        ))
+        )
+        .validate_next_header();
+        iter.in_sync = false;
+        iter
+    }
+
+    /// Byte offset of the next frame from the start of the stream, see
+    /// `position`.
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Continues with just the headers and their offsets, without reading
+    /// the side info, as for duration scans. Bytes between frames are
+    /// skipped as with `resync`, whether or not it was set.
+    pub fn headers_only(self) -> HeaderIter<'a> {
+        HeaderIter::with_base(self.data, self.offset)
+    }
+
+    /// Yields each frame together with where it starts, for building seek
+    /// tables, and its first sample, counted from the current frame. Frames
+    /// skipped by `lossy` are not counted.
+    pub fn with_positions(self) -> WithPositions<'a> {
+        WithPositions {
+            iter: self,
+            sample: 0,
+        }
+    }
+
+    /// Where the next frame starts. After a `TruncatedFinalFrame` error this
+    /// is the start of the truncated frame, so that it can be read again once
+    /// the rest of it has been fetched.
+    pub fn position(&self) -> Position {
+        Position {
+            offset: self.offset,
+            frame_index: self.frame_index,
+        }
+    }
+
+    /// Instead of yielding an error for bytes that don't start a valid frame
+    /// header, scan forward to the next header that does and resume there.
+    pub fn resync(mut self) -> Self {
+        self.resync = true;
+        self
+    }
+
+    /// Skip frames that fail to parse instead of yielding errors, resyncing
+    /// as needed. A truncated final frame ends the iteration.
+    pub fn lossy(mut self) -> Self {
+        self.resync = true;
+        self.lossy = true;
+        self
+    }
+
+    /// When resyncing, only accept a header if the frame it starts is followed
+    /// by another header consistent with it, or by the end of the data. This
+    /// rules out most sync words that occur by chance in garbage.
+    pub fn validate_next_header(mut self) -> Self {
+        self.resync = true;
+        self.validate = true;
+        self
+    }
+
+    /// Defaults to `ParseMode::Lenient`. The first frame, read by `new`, is
+    /// always read leniently.
+    pub fn parse_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn crc_policy(mut self, policy: CrcPolicy) -> Self {
+        self.crc_policy = policy;
+        self
+    }
+
+    /// Number of frames yielded so far despite a CRC mismatch.
+    pub fn crc_mismatches(&self) -> usize {
+        self.crc_mismatches
+    }
+
+    /// Number of bytes skipped so far, by resyncing or by skipping frames in lossy mode.
+    pub fn skipped_bytes(&self) -> usize {
+        self.skipped_bytes
+    }
+
+    /// Number of frames with a valid header that were skipped so far, in lossy
+    /// mode or for their CRC.
+    pub fn skipped_frames(&self) -> usize {
+        self.skipped_frames
+    }
+
+    fn advance(&mut self, bytes: usize) {
+        self.offset += bytes;
+        self.data = &self.data[bytes..];
+    }
+
+    fn discard(&mut self, bytes: usize) {
+        self.skipped_bytes += bytes;
+        self.in_sync = false;
+        self.advance(bytes);
+    }
+
+    fn next_header_matches(&self, header: &FrameHeader) -> bool {
+        match self.data.get(header.frame_bytes..) {
+            Some(rest) if rest.len() >= 4 => {
+                FrameHeader::read(rest).is_ok_and(|next| header.is_consistent_with(&next))
+            }
+            _ => true,
+        }
     }
 }
 
 impl<'a> Iterator for FrameIter<'a> {
-    type Item = Result<Frame<'a>, DecodingError>;
+    type Item = Result<Frame<'a>, StreamError>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.data.len() < 4 {
+                return None;
+            }
+
+            let mut crc_mismatch = false;
+            let result = Frame::read_in_with_mode(self.data, self.mode).and_then(|frame| {
+                // A header found by resyncing has to fit the stream so far
+                let consistent = self.in_sync
+                    || self
+                        .last_header
+                        .is_none_or(|last| last.is_consistent_with(&frame.header));
+                if !consistent
+                    || self.validate && !self.in_sync && !self.next_header_matches(&frame.header)
+                {
+                    Err((Structure::FrameHeader, DecodingError::InvalidFrameHeader))
+                } else if crc::crc_matches(&frame.header, self.data) {
+                    Ok(frame)
+                } else if self.crc_policy == CrcPolicy::Accept {
+                    crc_mismatch = true;
+                    Ok(frame)
+                } else {
+                    Err((Structure::SideInfo, DecodingError::CrcMismatch))
+                }
+            });
+
+            match result {
+                Ok(frame) => {
+                    self.crc_mismatches += crc_mismatch as usize;
+                    self.in_sync = true;
+                    self.last_header = Some(frame.header);
+                    self.advance(frame.header.frame_bytes);
+                    self.frame_index += 1;
+                    return Some(Ok(frame));
+                }
+                Err((_, err)) if self.resync && err.is_header_error() => {
+                    let skip =
+                        find_sync(&self.data[1..]).map_or(self.data.len(), |offset| offset + 1);
+                    self.discard(skip);
+                }
+                Err((_, DecodingError::UnexpectedEndOfStream)) if self.lossy => {
+                    self.skipped_frames += 1;
+                    self.frame_index += 1;
+                    self.discard(self.data.len());
+                }
+                Err((Structure::Frame, DecodingError::UnexpectedEndOfStream)) => {
+                    // The header was read, so this is the last frame and iteration ends here
+                    let error = StreamError {
+                        error: DecodingError::TruncatedFinalFrame {
+                            available: self.data.len(),
+                        },
+                        structure: Structure::Frame,
+                        offset: self.offset,
+                        frame_index: self.frame_index,
+                    };
+                    self.data = &[];
+                    return Some(Err(error));
+                }
+                Err((_, err))
+                    if self.lossy
+                        || err == DecodingError::CrcMismatch
+                            && self.crc_policy == CrcPolicy::Skip =>
+                {
+                    // The header was fine, so the frame length is known
+                    let frame_bytes =
+                        FrameHeader::read(self.data).map_or(1, |header| header.frame_bytes);
+                    self.skipped_frames += 1;
+                    self.frame_index += 1;
+                    self.discard(frame_bytes.min(self.data.len()));
+                }
+                Err((structure, error)) => {
+                    return Some(Err(StreamError {
+                        error,
+                        structure,
+                        offset: self.offset,
+                        frame_index: self.frame_index,
+                    }));
+                }
+            }
+        }
+    }
+
+    /// Exact if the first frame carried a Xing/Info or VBRI header with the
+    /// number of frames and the stream matches it. Frames skipped in lossy
+    /// mode count as iterated.
+    fn size_hint(&self) -> (usize, Option<usize>) {
         if self.data.len() < 4 {
-            return None;
+            return (0, Some(0));
+        }
+        match self.end_index {
+            Some(end_index) => {
+                let remaining = end_index.saturating_sub(self.frame_index);
+                (remaining, Some(remaining))
+            }
+            None => (0, None),
         }
+    }
+}
 
-        Some(Frame::read(self.data).inspect(|frame| {
-            self.data = &self.data[frame.header.frame_bytes..];
+/// A frame and where it starts in the stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameWithPos<'a> {
+    pub position: Position,
+    /// Index of the first sample of the frame, per channel.
+    pub sample: u64,
+    pub frame: Frame<'a>,
+}
+
+impl FrameWithPos<'_> {
+    /// Playback time at which the frame starts.
+    pub fn timestamp(&self) -> Duration {
+        let sampling_rate = self.frame.header.sampling_rate as u64;
+        Duration::from_secs(self.sample / sampling_rate)
+            + Duration::from_secs(self.sample % sampling_rate) / sampling_rate as u32
+    }
+}
+
+/// See `FrameIter::with_positions`.
+#[derive(Debug, Clone)]
+pub struct WithPositions<'a> {
+    iter: FrameIter<'a>,
+    /// Samples in the frames yielded so far.
+    sample: u64,
+}
+
+impl<'a> WithPositions<'a> {
+    pub fn into_inner(self) -> FrameIter<'a> {
+        self.iter
+    }
+}
+
+impl<'a> Iterator for WithPositions<'a> {
+    type Item = Result<FrameWithPos<'a>, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = match self.iter.next()? {
+            Ok(frame) => frame,
+            Err(err) => return Some(Err(err)),
+        };
+        // The iterator has just moved past the frame
+        let position = Position {
+            offset: self.iter.offset - frame.header.frame_bytes,
+            frame_index: self.iter.frame_index - 1,
+        };
+        let sample = self.sample;
+        self.sample += frame.header.samples_per_frame() as u64;
+        Some(Ok(FrameWithPos {
+            position,
+            sample,
+            frame,
         }))
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
 }
 
 #[cfg(test)]
@@ -171,7 +731,7 @@ mod tests {
         let (first_frame, iter) = FrameIter::new(&data).unwrap();
         assert!(matches!(first_frame, FirstFrame::Vbr(_, _)));
 
-        let expected_lengths = vec![731, 130, 365, /* EOS */ 9999];
+        let expected_lengths = [731, 130, 365, /* EOS */ 9999];
 
         for (frame, expected_len) in iter.zip(expected_lengths.iter()) {
             let Frame { header, .. } = frame.unwrap();
@@ -186,7 +746,7 @@ mod tests {
         let (first_frame, iter) = FrameIter::new(&data).unwrap();
         assert!(matches!(first_frame, FirstFrame::Vbr(_, _)));
 
-        let expected_bitrates = vec![
+        let expected_bitrates = [
             224, 48, 40, 40, 32, 40, 32, 32, 40, 32, 40, 32, 32, 40, 32, 32, 32, 32, 32, 128, 32,
             /* EOS */ 9999,
         ];
@@ -199,13 +759,490 @@ mod tests {
     }
 
     #[test]
-    fn test_read_bits() {
-        assert_eq!(read_bits(0xFFFFFFFF00000000, 0..32), 0xFFFFFFFF_u64);
-        assert_eq!(read_bits(0x00000000FFFFFFFF, 32..64), 0xFFFFFFFF_u64);
-        assert_eq!(read_bits(0x0000FFFFFFFF0000, 16..48), 0xFFFFFFFF_u64);
-        assert_eq!(read_bits(0x00000000FFFFFFFF, 0..32), 0x00000000_u64);
-        assert_eq!(read_bits(0xFFFFFFFF00000000, 32..64), 0x00000000_u64);
-        assert_eq!(read_bits(0xFFFF00000000FFFF, 16..48), 0x00000000_u64);
+    fn test_is_likely_silent() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        let silent: Vec<_> = iter
+            .map(|frame| frame.unwrap().is_likely_silent())
+            .collect();
+        // Only the final frame, which LAME pads with silence
+        let mut expected = vec![false; 21];
+        expected[20] = true;
+        assert_eq!(silent, expected);
+    }
+
+    #[test]
+    fn test_find_sync() {
+        assert_eq!(find_sync(b"\xFF\xFB\x90\xC4"), Some(0));
+        assert_eq!(find_sync(b"\x00\xFF\xFF\xFB\x00\xFF\xFB\x90\xC4"), Some(5));
+        assert_eq!(find_sync(b"\xFF\xFB\x90"), None);
+    }
+
+    #[test]
+    fn test_frame_iter_resync() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        let frame_offsets: Vec<usize> = iter
+            .map(|frame| frame.unwrap().header.frame_bytes)
+            .scan(0, |offset, len| {
+                *offset += len;
+                Some(*offset)
+            })
+            .collect();
+
+        // Garbage, including a header with a reserved bitrate, between the
+        // Xing frame and the first audio frame, and after the fifth audio frame
+        let first_frame_len = data.len() - frame_offsets.last().unwrap();
+        let garbage = b"\x00\xFF\xFF\xFB\xF0\xC4\x12";
+        let fifth_end = first_frame_len + frame_offsets[4];
+        let mut corrupted = data[..first_frame_len].to_vec();
+        corrupted.extend_from_slice(garbage);
+        corrupted.extend_from_slice(&data[first_frame_len..fifth_end]);
+        corrupted.extend_from_slice(garbage);
+        corrupted.extend_from_slice(&data[fifth_end..]);
+        // Trailing garbage without any sync
+        corrupted.extend_from_slice(b"\xFF\x00\x00\x00\x00");
+
+        // Without resync, the garbage is an error
+        let (_, mut iter) = FrameIter::new(&corrupted).unwrap();
+        assert_eq!(
+            iter.next().unwrap().err(),
+            Some(StreamError {
+                error: DecodingError::InvalidFrameHeader,
+                structure: Structure::FrameHeader,
+                offset: first_frame_len,
+                frame_index: 1,
+            })
+        );
+
+        let (_, iter) = FrameIter::new(&corrupted).unwrap();
+        let mut iter = iter.resync();
+        let frames: Vec<_> = iter.by_ref().map(|frame| frame.unwrap()).collect();
+        assert_eq!(frames.len(), frame_offsets.len());
+        assert_eq!(iter.skipped_bytes(), 2 * garbage.len() + 5);
+    }
+
+    #[test]
+    fn test_frame_iter_resync_consistency() {
+        let data = read("tests/sine_320hz_50ms_vbr_frame1-3.mp3").unwrap();
+        // An MPEG-2 header in garbage within an MPEG-1 stream
+        let mut garbage = b"\x00\x00\xFF\xF3\x80\xC4".to_vec();
+        garbage.resize(garbage.len() + 20, 0);
+        let mut corrupted = data[..731].to_vec();
+        corrupted.extend_from_slice(&garbage);
+        corrupted.extend_from_slice(&data[731..]);
+
+        let (_, iter) = FrameIter::new(&corrupted).unwrap();
+        let mut iter = iter.resync();
+        let lengths: Vec<_> = iter
+            .by_ref()
+            .map(|frame| frame.unwrap().header.frame_bytes)
+            .collect();
+        assert_eq!(lengths, [130, 365]);
+        assert_eq!(iter.skipped_bytes(), garbage.len());
+    }
+
+    #[test]
+    fn test_frame_iter_validate_next_header() {
+        let data = read("tests/sine_320hz_50ms_vbr_frame1-3.mp3").unwrap();
+        // A sync word by chance, in garbage between the first two frames
+        let mut garbage = b"\x00\x00\xFF\xFB\x90\xC4".to_vec();
+        garbage.resize(garbage.len() + 20, 0);
+        let mut corrupted = data[..731].to_vec();
+        corrupted.extend_from_slice(&garbage);
+        corrupted.extend_from_slice(&data[731..]);
+
+        // Read as a frame, it swallows the second frame
+        let (_, iter) = FrameIter::new(&corrupted).unwrap();
+        let frame = iter.resync().next().unwrap().unwrap();
+        assert_eq!(frame.header.frame_bytes, 417);
+
+        let (_, iter) = FrameIter::new(&corrupted).unwrap();
+        let mut iter = iter.validate_next_header();
+        let lengths: Vec<_> = iter
+            .by_ref()
+            .map(|frame| frame.unwrap().header.frame_bytes)
+            .collect();
+        assert_eq!(lengths, [130, 365]);
+        assert_eq!(iter.skipped_bytes(), garbage.len());
+    }
+
+    #[test]
+    fn test_frame_iter_resume() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        let expected: Vec<_> = iter.map(|frame| frame.unwrap().main_data).collect();
+
+        // Fetch the file in ranges of 1000 bytes
+        let (_, mut iter) = FrameIter::new(&data[..1000]).unwrap();
+        let mut frames = Vec::new();
+        loop {
+            for frame in iter.by_ref() {
+                match frame {
+                    Ok(frame) => frames.push(frame.main_data),
+                    Err(err) => assert!(matches!(
+                        err.error,
+                        DecodingError::TruncatedFinalFrame { .. }
+                    )),
+                }
+            }
+            let position = iter.position();
+            if position.offset == data.len() {
+                break;
+            }
+            let end = (position.offset + 1000).min(data.len());
+            iter = FrameIter::resume(&data[position.offset..end], position);
+        }
+
+        assert_eq!(frames, expected);
+        assert_eq!(iter.position().frame_index, 22);
+    }
+
+    #[test]
+    fn test_frame_iter_parse_mode() {
+        let mut data = read("tests/sine_320hz_50ms_vbr_frame1-3.mp3").unwrap();
+        // Reserved emphasis on the second frame
+        data[731 + 3] = data[731 + 3] & !0b11 | 0b10;
+
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        assert!(iter.take(2).all(|frame| frame.is_ok()));
+
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        let mut iter = iter.parse_mode(ParseMode::Strict);
+        assert_eq!(
+            iter.next().unwrap().err().map(|err| err.error),
+            Some(DecodingError::UnsupportedEmphasis)
+        );
+    }
+
+    #[test]
+    fn test_frame_iter_positions() {
+        let data = read("tests/sine_320hz_50ms_vbr.mp3").unwrap();
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        assert_eq!(iter.byte_offset(), 417);
+        let positions: Vec<_> = iter
+            .with_positions()
+            .map(|frame| frame.unwrap().position)
+            .collect();
+        let offsets = [417, 417 + 731, 417 + 731 + 130];
+        for (index, (position, offset)) in positions.iter().zip(offsets).enumerate() {
+            assert_eq!(position.offset, offset);
+            assert_eq!(position.frame_index, index + 1);
+        }
+
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        let frames: Vec<_> = iter.with_positions().map(Result::unwrap).collect();
+        assert_eq!(frames[2].sample, 2 * 1152);
+        assert_eq!(frames[2].timestamp(), Duration::from_secs(2 * 1152) / 44100);
+
+        // Skipped bytes and frames are accounted for
+        let mut corrupted = data[..417 + 731].to_vec();
+        corrupted.extend_from_slice(b"\x00\x00\x00");
+        corrupted.extend_from_slice(&data[417 + 731..]);
+        let (_, iter) = FrameIter::new(&corrupted).unwrap();
+        let mut iter = iter.resync().with_positions();
+        iter.next().unwrap().unwrap();
+        let second = iter.next().unwrap().unwrap();
+        assert_eq!(second.position.offset, 417 + 731 + 3);
+        assert_eq!(second.position.frame_index, 2);
+        assert_eq!(second.frame.header.frame_bytes, 130);
+        assert_eq!(iter.into_inner().byte_offset(), corrupted.len() - 365);
+    }
+
+    #[test]
+    fn test_read_at() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (offset, frame) = Frame::read_at(&data, 3228 + 1).unwrap();
+        assert_eq!(offset, 3645);
+        assert_eq!(frame.header.frame_bytes, data.len() - 3645);
+        assert_eq!(Frame::read_at(&data, 0).unwrap().0, 0);
+        assert_eq!(
+            Frame::read_at(&data, 3646).err(),
+            Some(DecodingError::UnexpectedEndOfStream)
+        );
+
+        let mut iter = FrameIter::starting_at(&data, 1000);
+        assert_eq!(
+            iter.next().unwrap().unwrap().header.frame_bytes,
+            1304 - 1148
+        );
+        assert_eq!(
+            iter.position(),
+            Position {
+                offset: 1304,
+                frame_index: 1,
+            }
+        );
+        assert_eq!(iter.count(), 19);
+    }
+
+    #[test]
+    fn test_frame_iter_size_hint() {
+        let data = read("tests/sine_320hz_50ms_vbr.mp3").unwrap();
+        let (_, mut iter) = FrameIter::new(&data).unwrap();
+        assert_eq!(iter.size_hint(), (3, Some(3)));
+        iter.next().unwrap().unwrap();
+        assert_eq!(iter.size_hint(), (2, Some(2)));
+        assert_eq!(iter.by_ref().count(), 2);
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+
+        let data = read("tests/sine_320hz_50ms_vbr_frame1-3.mp3").unwrap();
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        assert_eq!(iter.size_hint(), (0, None));
+    }
+
+    #[test]
+    fn test_frame_iter_lossy() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (first_frame, iter) = FrameIter::new(&data).unwrap();
+        let frame_count = iter.count();
+
+        // Corrupt the block type of the second audio frame: window switching
+        // with block type 0 is invalid
+        let second_frame = first_frame.len() + 731;
+        let mut corrupted = data.clone();
+        corrupted[second_frame + 4 + 6] |= 0b0001_0000;
+        corrupted[second_frame + 4 + 6] &= !0b0000_1100;
+        // Truncate the last frame
+        corrupted.truncate(corrupted.len() - 10);
+
+        let (_, mut iter) = FrameIter::new(&corrupted).unwrap();
+        assert!(iter.next().unwrap().is_ok());
+        assert_eq!(
+            iter.next().unwrap().err(),
+            Some(StreamError {
+                error: DecodingError::InvalidBlockType,
+                structure: Structure::SideInfo,
+                offset: second_frame,
+                frame_index: 2,
+            })
+        );
+
+        let (_, iter) = FrameIter::new(&corrupted).unwrap();
+        let mut iter = iter.lossy();
+        let frames: Vec<_> = iter.by_ref().map(|frame| frame.unwrap()).collect();
+        assert_eq!(frames.len(), frame_count - 2);
+        assert_eq!(iter.skipped_frames(), 2);
+    }
+
+    #[test]
+    fn test_stream_error_location() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+
+        let mut corrupted = data.clone();
+        corrupted[0x15..0x19].copy_from_slice(b"Info");
+        corrupted.truncate(0x40);
+        assert_eq!(
+            FrameIter::new(&corrupted).err(),
+            Some(StreamError {
+                error: DecodingError::UnexpectedEndOfStream,
+                structure: Structure::Frame,
+                offset: 0,
+                frame_index: 0,
+            })
+        );
+
+        let truncated = &data[..data.len() - 1];
+        let (_, mut iter) = FrameIter::new(truncated).unwrap();
+        let last = iter.find_map(Result::err).unwrap();
+        let last_frame = FrameHeader::read(&truncated[last.offset..]).unwrap();
+        assert_eq!(
+            last,
+            StreamError {
+                error: DecodingError::TruncatedFinalFrame {
+                    available: last_frame.frame_bytes - 1,
+                },
+                structure: Structure::Frame,
+                offset: truncated.len() - (last_frame.frame_bytes - 1),
+                frame_index: 21,
+            }
+        );
+        assert!(iter.next().is_none());
+    }
+
+    /// Runs every parser over `data`, which only has to not panic.
+    fn parse_everything(data: &[u8]) {
+        let _ = probe(data);
+        if let Ok((_, iter)) = FrameIter::new(data) {
+            // Without lossy mode, errors repeat forever
+            iter.take(100).for_each(drop);
+        }
+        if let Ok((_, iter)) = FrameIter::new(data) {
+            iter.lossy().for_each(drop);
+        }
+        let mut parser = StreamParser::new().resync();
+        parser.feed(data);
+        for _ in 0..100 {
+            if parser.next_frame().is_none() {
+                break;
+            }
+        }
+        let _ = (parser.vbr_info(), parser.vbri_info());
+        if let Ok(header) = FrameHeader::read(data) {
+            let _ = SideInfo::read(&header, data.get(header.len()..).unwrap_or_default());
+        }
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        let files = [
+            "tests/sine_320hz_50ms_vbr.mp3",
+            "tests/sine_320hz_50ms_vbr_frame0.mp3",
+            "tests/lavf_id3v2_mpeg2_22050hz.mp3",
+        ];
+        // xorshift, to mutate the files reproducibly
+        let mut state = 0x2545_F491_4F6C_DD1D_u64;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as usize
+        };
+
+        for file in files {
+            let data = read(file).unwrap();
+            for len in 0..data.len().min(1200) {
+                parse_everything(&data[..len]);
+            }
+            for _ in 0..300 {
+                let mut mutated = data.clone();
+                for _ in 0..1 + random() % 8 {
+                    let index = random() % mutated.len();
+                    mutated[index] = random() as u8;
+                }
+                mutated.truncate(random() % (data.len() + 1));
+                parse_everything(&mutated);
+            }
+        }
+
+        // Headers that claim more than is there
+        assert_eq!(
+            read_u32(&mut &[0xFF, 0xFB][..]),
+            Err(DecodingError::UnexpectedEndOfStream)
+        );
+        let header = FrameHeader::read(b"\xFF\xFB\x90\xC4").unwrap();
+        assert_eq!(
+            SideInfo::read(&header, &[0; 16]).err(),
+            Some(DecodingError::UnexpectedEndOfStream)
+        );
+        let mut xing = b"\xFF\xFB\x90\xC4".to_vec();
+        xing.resize(4 + 17, 0);
+        xing.extend_from_slice(b"Xing\0\0\0\x07\0\0\0\x03\0\0\x06\x6B\0\x98");
+        assert!(matches!(
+            VbrInfo::read(&header, &xing[4..]),
+            Some(Err(DecodingError::UnexpectedEndOfStream))
+        ));
+    }
+
+    #[test]
+    fn test_error_display() {
+        let error = StreamError {
+            error: DecodingError::InvalidBlockType,
+            structure: Structure::SideInfo,
+            offset: 1148,
+            frame_index: 2,
+        };
+        assert_eq!(
+            error.to_string(),
+            "invalid block type in side info of frame 2 at byte 1148"
+        );
+
+        assert_eq!(
+            DecodingError::TruncatedFinalFrame { available: 100 }.to_string(),
+            "final frame truncated to 100 bytes"
+        );
+
+        let boxed: Box<dyn Error> = Box::new(error);
+        assert_eq!(boxed.source().unwrap().to_string(), "invalid block type");
+    }
+
+    #[test]
+    fn test_send_sync() {
+        // Parsing state can be handed to worker threads
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Frame>();
+        assert_send_sync::<LazyFrame>();
+        assert_send_sync::<FirstFrame>();
+        assert_send_sync::<FrameIter>();
+        assert_send_sync::<WithPositions>();
+        assert_send_sync::<StreamParser>();
+        assert_send_sync::<Reservoir>();
+        assert_send_sync::<SampleProducer>();
+        assert_send_sync::<SampleConsumer>();
+        assert_send_sync::<ScanReport>();
+        assert_send_sync::<HeaderIter>();
+        assert_send_sync::<RevFrameIter>();
+        assert_send_sync::<SeekIndex>();
+        assert_send_sync::<Metadata>();
+        assert_send_sync::<FrameWriter<Vec<u8>>>();
+        assert_send_sync::<IcyReader<std::fs::File>>();
+        assert_send_sync::<StreamError>();
+    }
+
+    #[test]
+    fn test_granules() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (_, mut iter) = FrameIter::new(&data).unwrap();
+        let frame = iter.next().unwrap().unwrap();
+        let granules: Vec<_> = frame.granules().collect();
+        assert_eq!(granules.len(), 2);
+        assert_eq!(granules[1].granule_index, 1);
+        assert_eq!(
+            granules[1].granule,
+            &frame.side_info.granule1().unwrap().granules()[0]
+        );
+
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        let (first_frame, _) = FrameIter::new(&data[32..]).unwrap();
+        let FirstFrame::Cbr(frame) = first_frame else {
+            panic!("Expected CBR frame")
+        };
+        assert_eq!(frame.granules().count(), 1);
+    }
+
+    #[test]
+    fn test_headers_only() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (_, mut iter) = FrameIter::new(&data).unwrap();
+        iter.next().unwrap().unwrap();
+        let headers: Vec<_> = iter.headers_only().collect();
+        assert_eq!(headers.len(), 20);
+        assert_eq!(headers[0].0, 1148);
+        assert_eq!(headers, HeaderIter::new(&data).skip(2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_lazy_frame() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let lazy = LazyFrame::read(&data[417..]).unwrap();
+        let frame = Frame::read(&data[417..]).unwrap();
+        assert_eq!(lazy.header, frame.header);
+        assert_eq!(lazy.side_info(), Ok(frame.side_info.clone()));
+        assert_eq!(lazy.main_data(), frame.main_data);
+        assert_eq!(lazy.frame(), Ok(frame));
+
+        let frames: Vec<_> = HeaderIter::new(&data).lazy_frames().collect();
+        assert_eq!(frames.len(), 22);
+        assert_eq!(frames[1], (417, lazy));
+        // Damaged side info is only an error when it is read
+        let mut damaged = data[417..1148].to_vec();
+        // Block type 0 with window switching, in the first granule
+        assert_ne!(damaged[10] & 0x10, 0);
+        damaged[10] &= !0x0C;
+        let lazy = LazyFrame::read(&damaged).unwrap();
+        assert_eq!(lazy.side_info(), Err(DecodingError::InvalidBlockType));
+    }
+
+    #[test]
+    fn test_clone_iter() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (first_frame, mut iter) = FrameIter::new(&data).unwrap();
+        assert_eq!(first_frame.clone(), first_frame);
+        iter.next().unwrap().unwrap();
+        // A clone carries on from the same frame
+        let mut clone = iter.clone();
+        assert_eq!(clone.next(), iter.next());
+        assert!(format!("{:?}", iter.next().unwrap().unwrap()).starts_with("Frame {"));
     }
 
     #[test]
@@ -221,11 +1258,4 @@ mod tests {
         assert_eq!(read_u32(&mut data).unwrap(), 0x89AB_CDEF_u32);
         assert_eq!(data.len(), 0);
     }
-
-    #[test]
-    fn test_read_u64() {
-        let mut data = b"\x89\xAB\xCD\xEF\x01\x23\x45\x67".as_slice();
-        assert_eq!(read_u64(&mut data).unwrap(), 0x89AB_CDEF_0123_4567_u64);
-        assert_eq!(data.len(), 0);
-    }
 }
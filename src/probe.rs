@@ -0,0 +1,134 @@
+use crate::{
+    DecodingError, FirstFrame, Frame, FrameHeader, encoder::VbrHeader, find_sync, id3::id3v2_len,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Probe {
+    /// Offset of the first frame, after any leading ID3v2 tags and any bytes
+    /// between them and the frame that can't start one.
+    pub first_frame_offset: usize,
+    /// Offset of the first audio frame. This is past the first frame if it is
+    /// a Xing/Info/VBRI metadata frame, and equal to `first_frame_offset` otherwise.
+    pub audio_offset: usize,
+    pub vbr_header: VbrHeader,
+}
+
+/// Offset of the first frame at or after `from` that reads as a first frame
+/// and is followed by a consistent header or the end of the data, as
+/// `FrameIter::validate_next_header` accepts them.
+fn resync_first_frame(data: &[u8], from: usize) -> Option<usize> {
+    let mut offset = from;
+    while let Some(sync) = data.get(offset..).and_then(find_sync) {
+        offset += sync;
+        let rest = &data[offset..];
+        if let Ok(first) = Frame::read_first(rest) {
+            let header = first.header();
+            let next = rest.get(header.frame_bytes..).unwrap_or_default();
+            if next.len() < 4
+                || FrameHeader::read(next).is_ok_and(|next| header.is_consistent_with(&next))
+            {
+                return Some(offset);
+            }
+        }
+        offset += 1;
+    }
+    None
+}
+
+/// Locates the start of the audio in a complete MP3 file. Bytes that can't
+/// start a frame, after the leading ID3v2 tags, are skipped by resyncing.
+pub fn probe(data: &[u8]) -> Result<Probe, DecodingError> {
+    let mut first_frame_offset = 0;
+    // Some taggers prepend a new tag without removing the old one
+    while let Some(len) = id3v2_len(&data[first_frame_offset..]) {
+        first_frame_offset += len;
+        if first_frame_offset > data.len() {
+            return Err(DecodingError::UnexpectedEndOfStream);
+        }
+    }
+
+    if let Err(err) = Frame::read_first(&data[first_frame_offset..]) {
+        first_frame_offset = resync_first_frame(data, first_frame_offset).ok_or(err)?;
+    }
+    let (vbr_header, metadata_len) = match Frame::read_first(&data[first_frame_offset..])? {
+        FirstFrame::Vbr(header, vbr_info) if vbr_info.is_info() => {
+            (VbrHeader::Info, header.frame_bytes)
+        }
+        FirstFrame::Vbr(header, _) => (VbrHeader::Xing, header.frame_bytes),
+        FirstFrame::Vbri(header, _) => (VbrHeader::Vbri, header.frame_bytes),
+        FirstFrame::Cbr(_) => (VbrHeader::None, 0),
+    };
+
+    Ok(Probe {
+        first_frame_offset,
+        audio_offset: first_frame_offset + metadata_len,
+        vbr_header,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Reader, SeekIndex};
+
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_probe_xing() {
+        let data = read("tests/sine_320hz_50ms_vbr.mp3").unwrap();
+        assert_eq!(
+            probe(&data),
+            Ok(Probe {
+                first_frame_offset: 0,
+                audio_offset: 417,
+                vbr_header: VbrHeader::Xing,
+            })
+        );
+    }
+
+    #[test]
+    fn test_probe_id3v2() {
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        assert_eq!(
+            probe(&data),
+            Ok(Probe {
+                first_frame_offset: 32,
+                audio_offset: 32,
+                vbr_header: VbrHeader::None,
+            })
+        );
+
+        let mut twice = data[..32].to_vec();
+        twice.extend_from_slice(&data);
+        assert_eq!(probe(&twice).unwrap().audio_offset, 64);
+
+        assert_eq!(
+            probe(&data[..20]),
+            Err(DecodingError::UnexpectedEndOfStream)
+        );
+    }
+
+    #[test]
+    fn test_probe_junk() {
+        let data = read("tests/sine_320hz_50ms_vbr.mp3").unwrap();
+        // Including a sync word that no consistent header follows
+        let mut junk = b"ID3\x04\0\0\0\0\0\0junk\xFF\xFB\x90\x64 and more".to_vec();
+        let junk_len = junk.len();
+        junk.extend_from_slice(&data);
+        assert_eq!(
+            probe(&junk),
+            Ok(Probe {
+                first_frame_offset: junk_len,
+                audio_offset: junk_len + 417,
+                vbr_header: VbrHeader::Xing,
+            })
+        );
+        assert_eq!(Reader::new(&junk).unwrap().count(), 3);
+        assert_eq!(SeekIndex::build(&junk).unwrap().points().len(), 1);
+
+        assert_eq!(
+            probe(b"junk without any frames"),
+            Err(DecodingError::InvalidFrameHeader)
+        );
+    }
+}
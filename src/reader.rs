@@ -0,0 +1,505 @@
+use std::time::Duration;
+
+use crate::{
+    BitrateMode, DecodingError, FirstFrame, Frame, FrameIter, HeaderIter, Position, ScanReport,
+    StreamError, find_sync, header::FrameHeader, probe, reservoir::priming_start,
+};
+
+/// Reads the frames of a complete MP3 file, skipping leading ID3v2 tags and
+/// resyncing over damage, and seeks within it.
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    /// The file from the first frame on. Offsets are relative to this.
+    audio: &'a [u8],
+    first_frame: FirstFrame<'a>,
+    iter: FrameIter<'a>,
+}
+
+impl<'a> Reader<'a> {
+    /// Frames `bitrate_mode` reads to tell CBR from VBR files without a
+    /// header. VBR encoders vary the bitrate within the first second or so.
+    pub const BITRATE_SCAN_FRAMES: usize = 64;
+
+    /// The encoder delay of LAME, which `encoder_delay` assumes when it can
+    /// only tell that there is one.
+    pub const TYPICAL_ENCODER_DELAY: u16 = 576;
+    /// How much quieter than the granules after it `encoder_delay` requires
+    /// the first one to be to assume a delay.
+    const PRIMING_RATIO: f64 = 8.0;
+    /// How many frames before the target `seek_to_sample` aims the seek table
+    /// at, to leave room for the priming frames.
+    const SEEK_MARGIN_FRAMES: usize = 8;
+
+    pub fn new(data: &'a [u8]) -> Result<Self, DecodingError> {
+        let audio = &data[probe(data)?.first_frame_offset..];
+        let (first_frame, iter) = FrameIter::new(audio).map_err(|err| err.error)?;
+        Ok(Reader {
+            audio,
+            first_frame,
+            iter: iter.resync(),
+        })
+    }
+
+    pub fn first_frame(&self) -> &FirstFrame<'a> {
+        &self.first_frame
+    }
+
+    /// Byte offset of the next frame, relative to the first frame.
+    pub fn byte_offset(&self) -> usize {
+        self.iter.byte_offset()
+    }
+
+    fn samples_per_frame(&self) -> u32 {
+        self.first_frame.header().samples_per_frame()
+    }
+
+    /// Offset of the first frame starting at or after `from`, validated
+    /// against the header following it.
+    fn frame_start(&self, from: usize) -> usize {
+        let first_header = self.first_frame.header();
+        let mut offset = from;
+        while let Some(sync) = self.audio.get(offset..).and_then(find_sync) {
+            offset += sync;
+            let rest = &self.audio[offset..];
+            let fits = |header: &FrameHeader| first_header.is_consistent_with(header);
+            if let Some(header) = FrameHeader::read(rest).ok().filter(fits) {
+                let next = rest.get(header.frame_bytes..).unwrap_or_default();
+                if next.len() < 4 || FrameHeader::read(next).is_ok_and(|next| fits(&next)) {
+                    return offset;
+                }
+            }
+            offset += 1;
+        }
+        self.audio.len()
+    }
+
+    /// Number of frames before the first audio frame.
+    fn header_frames(&self) -> usize {
+        match self.first_frame {
+            FirstFrame::Vbr(..) | FirstFrame::Vbri(..) => 1,
+            FirstFrame::Cbr(_) => 0,
+        }
+    }
+
+    /// Offsets and headers of the audio frames, found by reading only their
+    /// headers.
+    fn scan_frames(&self) -> impl Iterator<Item = (usize, FrameHeader)> + '_ {
+        let start = if self.header_frames() == 1 {
+            self.first_frame.len()
+        } else {
+            0
+        };
+        HeaderIter::new(&self.audio[start..]).map(move |(offset, header)| (start + offset, header))
+    }
+
+    /// Number of PCM samples per channel in the file, without the encoder
+    /// delay and padding if the LAME tag records them, unlike the samples
+    /// `seek_to_sample` counts, which start with the delay. Taken from the
+    /// Xing or VBRI header if there is one, and counted by scanning the frame
+    /// headers otherwise.
+    pub fn total_samples(&self) -> u64 {
+        let from_header = match &self.first_frame {
+            FirstFrame::Vbr(_, vbr_info) => vbr_info.total_samples(),
+            FirstFrame::Vbri(_, vbri_info) => {
+                Some(vbri_info.frames() as u64 * self.samples_per_frame() as u64)
+            }
+            FirstFrame::Cbr(_) => None,
+        };
+        from_header
+            .unwrap_or_else(|| self.scan_frames().count() as u64 * self.samples_per_frame() as u64)
+    }
+
+    /// Average bitrate in bit/s, unlike `FrameHeader::bitrate`, which is in
+    /// kbit/s. Taken from the file size and duration in the Xing or VBRI
+    /// header if it has both, and computed by scanning the frame headers
+    /// otherwise. Returns `None` for a file without audio frames.
+    pub fn average_bitrate(&self) -> Option<u32> {
+        let from_header = match &self.first_frame {
+            FirstFrame::Vbr(_, vbr_info) => vbr_info.filesize().zip(vbr_info.duration()),
+            FirstFrame::Vbri(_, vbri_info) => Some((vbri_info.filesize(), vbri_info.duration())),
+            FirstFrame::Cbr(_) => None,
+        };
+        let (bytes, duration) = match from_header {
+            Some((bytes, duration)) if bytes > 0 && !duration.is_zero() => (bytes as u64, duration),
+            _ => {
+                let (frames, bytes) = self
+                    .scan_frames()
+                    .fold((0, 0), |(frames, bytes), (_, header)| {
+                        (frames + 1, bytes + header.frame_bytes as u64)
+                    });
+                (bytes, self.first_frame.header().duration() * frames)
+            }
+        };
+        if duration.is_zero() {
+            return None;
+        }
+        Some((bytes as f64 * 8.0 / duration.as_secs_f64()).round() as u32)
+    }
+
+    /// Encoder delay in samples, from the LAME tag, or else guessed from the
+    /// side info of the first granules, so that files without the tag can
+    /// still be trimmed for gapless playback. Encoders that prime with
+    /// silence leave the first granule much quieter than the ones after it;
+    /// such files are taken to have the delay of LAME and the encoders based
+    /// on it, `TYPICAL_ENCODER_DELAY`. Returns `None` if neither tells.
+    pub fn encoder_delay(&self) -> Option<u16> {
+        if let FirstFrame::Vbr(_, vbr_info) = &self.first_frame
+            && let Some(lame) = vbr_info.lame()
+        {
+            return Some(lame.encoder_delay);
+        }
+
+        let (first_frame, iter) = FrameIter::new(self.audio).ok()?;
+        let first_frame = match first_frame {
+            FirstFrame::Cbr(frame) => Some(frame),
+            _ => None,
+        };
+        let mut bounds = first_frame
+            .into_iter()
+            .chain(iter.map_while(Result::ok))
+            .flat_map(|frame| {
+                frame
+                    .side_info
+                    .granule_amplitude_bounds()
+                    .collect::<Vec<_>>()
+            });
+        let first = bounds.next()?;
+        let next = bounds.take(2).fold(0.0, f64::max);
+        (first * Self::PRIMING_RATIO < next).then_some(Self::TYPICAL_ENCODER_DELAY)
+    }
+
+    /// Collects statistics over the audio frames, reading only their headers.
+    pub fn scan_report(&self) -> ScanReport {
+        ScanReport::from_headers(self.scan_frames().map(|(_, header)| header))
+    }
+
+    /// See `FirstFrame::bitrate_mode`. Files without a header are taken to be
+    /// constant if the first `BITRATE_SCAN_FRAMES` frames share a bitrate.
+    pub fn bitrate_mode(&self) -> BitrateMode {
+        self.first_frame.bitrate_mode().unwrap_or_else(|| {
+            let bitrate = self.first_frame.header().bitrate;
+            let constant = self
+                .scan_frames()
+                .take(Self::BITRATE_SCAN_FRAMES)
+                .all(|(_, header)| header.bitrate == bitrate);
+            if constant {
+                BitrateMode::Constant
+            } else {
+                BitrateMode::Variable
+            }
+        })
+    }
+
+    /// Seeks so that decoding from the next frame reaches `sample` exactly,
+    /// and returns how many samples to decode and discard before it. This
+    /// lands early enough for the decoder to have the bit reservoir data and
+    /// the overlap of the frame it starts in. `sample` counts from the start
+    /// of the first audio frame, including the encoder delay, unlike
+    /// `total_samples`. The frames before are counted by reading their
+    /// headers only; the seek table of the Xing or VBRI header, if any, tells
+    /// where the frames whose contents are read start. Returns `None`,
+    /// without seeking, if the stream ends before `sample`.
+    pub fn seek_to_sample(&mut self, sample: u64) -> Option<u64> {
+        let samples_per_frame = self.samples_per_frame() as u64;
+        let target = (sample / samples_per_frame) as usize;
+
+        // The table only places frames roughly, so the frames before the one
+        // it points to are counted
+        let aim = target.saturating_sub(Self::SEEK_MARGIN_FRAMES);
+        let frame_duration = self.first_frame.header().duration();
+        let toc_offset = self
+            .toc_landing(frame_duration * aim as u32)
+            .map_or(0, |(offset, _)| offset);
+        let (start_frame, (start, _)) = self
+            .scan_frames()
+            .enumerate()
+            .find(|&(index, (offset, _))| offset >= toc_offset || index == aim)?;
+
+        let offsets: Vec<_> = HeaderIter::new(&self.audio[start..])
+            .map(|(offset, _)| start + offset)
+            .take(target - start_frame + 1)
+            .collect();
+        if offsets.len() <= target - start_frame {
+            return None;
+        }
+
+        let primed = priming_start(self.audio, &offsets, target - start_frame)?;
+        let landing = start_frame + primed;
+
+        let position = Position {
+            offset: offsets[primed],
+            frame_index: landing + self.header_frames(),
+        };
+        self.iter = FrameIter::resume(&self.audio[position.offset..], position).resync();
+
+        Some(sample - landing as u64 * samples_per_frame)
+    }
+
+    /// Jumps close to `time` using the seek table of the Xing or VBRI header,
+    /// and returns the time at which the next frame starts. Files without a
+    /// seek table are scanned as with `seek_to_sample`, except that the next
+    /// frame is the one containing `time`. Returns `None`, without seeking, if
+    /// the file has neither a seek table nor audio at `time`.
+    pub fn seek_to(&mut self, time: Duration) -> Option<Duration> {
+        if let FirstFrame::Cbr(_) = self.first_frame {
+            return self.scan_to(time);
+        }
+        let (landing, landing_time) = self.toc_landing(time)?;
+
+        let frame_duration = self.first_frame.header().duration();
+        let frame_index = (landing_time.as_secs_f64() / frame_duration.as_secs_f64()).round();
+        let position = Position {
+            offset: landing,
+            frame_index: frame_index as usize + 1,
+        };
+        self.iter = FrameIter::resume(&self.audio[landing..], position).resync();
+
+        Some(landing_time)
+    }
+}
+
+impl Reader<'_> {
+    /// Offset of the frame the seek table of the Xing or VBRI header points
+    /// to for `time`, and the time at which it starts. Returns `None` for
+    /// files without a seek table.
+    fn toc_landing(&self, time: Duration) -> Option<(usize, Duration)> {
+        let (target, duration) = match &self.first_frame {
+            FirstFrame::Vbr(_, vbr_info) => {
+                let duration = vbr_info.duration()?;
+                let fraction = time.as_secs_f64() / duration.as_secs_f64();
+                (
+                    vbr_info.byte_offset_for_fraction(fraction as f32)?,
+                    duration,
+                )
+            }
+            FirstFrame::Vbri(_, vbri_info) => {
+                let duration = vbri_info.duration();
+                let fraction = time.as_secs_f64() / duration.as_secs_f64();
+                (
+                    vbri_info.byte_offset_for_fraction(fraction as f32)?,
+                    duration,
+                )
+            }
+            FirstFrame::Cbr(_) => return None,
+        };
+
+        // The TOC may point into the header frame itself
+        let landing = self.frame_start(target.max(self.first_frame.len()));
+        let landing_time = match &self.first_frame {
+            FirstFrame::Vbr(_, vbr_info) => vbr_info.time_for_byte(landing),
+            FirstFrame::Vbri(_, vbri_info) => vbri_info.time_for_byte(landing),
+            FirstFrame::Cbr(_) => None,
+        }
+        .unwrap_or(duration);
+        Some((landing, landing_time))
+    }
+
+    fn scan_to(&mut self, time: Duration) -> Option<Duration> {
+        let sampling_rate = self.first_frame.header().sampling_rate as u64;
+        let samples_per_frame = self.samples_per_frame() as u64;
+        let sample = (time.as_secs_f64() * sampling_rate as f64) as u64;
+        let target = sample / samples_per_frame;
+
+        self.seek_to_sample(sample)?;
+        // Skip the priming frames, whose headers were all just read
+        let landing = self.iter.position().frame_index - self.header_frames();
+        let skip = target - landing as u64;
+        self.iter.by_ref().take(skip as usize).for_each(drop);
+
+        Some(Duration::from_secs(target * samples_per_frame) / sampling_rate as u32)
+    }
+}
+
+impl<'a> Iterator for Reader<'a> {
+    type Item = Result<Frame<'a>, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Reservoir;
+
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_seek_to() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        let offsets: Vec<_> = iter
+            .with_positions()
+            .map(|frame| frame.unwrap().position.offset)
+            .collect();
+        let frame_duration = Duration::from_secs(1152) / 44100;
+
+        let mut reader = Reader::new(&data).unwrap();
+        for millis in [0, 100, 250, 400, 500] {
+            let target = Duration::from_millis(millis);
+            let landed = reader.seek_to(target).unwrap();
+            assert!(landed.abs_diff(target) < 2 * frame_duration, "{landed:?}");
+            let offset = reader.byte_offset();
+            assert!(offsets.contains(&offset));
+            reader.next().unwrap().unwrap();
+        }
+
+        // Seeking past the end lands there
+        let duration = reader.seek_to(Duration::from_secs(10)).unwrap();
+        assert_eq!(
+            Some(duration),
+            match reader.first_frame() {
+                FirstFrame::Vbr(_, vbr_info) => vbr_info.duration(),
+                _ => None,
+            }
+        );
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_seek_to_sample() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        let offsets: Vec<_> = iter
+            .with_positions()
+            .map(|frame| frame.unwrap().position.offset)
+            .collect();
+
+        let mut reader = Reader::new(&data).unwrap();
+        for frame in [0, 1, 5, 12, 20] {
+            let sample = frame * 1152 + 100;
+            let discard = reader.seek_to_sample(sample).unwrap();
+            assert_eq!(discard % 1152, 100);
+            let landing = ((sample - discard) / 1152) as usize;
+            assert!(landing < frame as usize || frame == 0);
+            assert_eq!(reader.byte_offset(), offsets[landing]);
+
+            // The bit reservoir is filled by the time the target frame comes
+            let mut reservoir = Reservoir::new();
+            let frames: Vec<_> = reader.by_ref().take(frame as usize + 1 - landing).collect();
+            for (i, audio_frame) in frames.iter().enumerate() {
+                let main_data = reservoir.push(audio_frame.as_ref().unwrap());
+                if landing + i == frame as usize {
+                    assert!(main_data.is_some());
+                }
+            }
+        }
+
+        assert_eq!(reader.seek_to_sample(21 * 1152), None);
+    }
+
+    #[test]
+    fn test_seek_to_sample_with_toc() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let mut reader = Reader::new(&data).unwrap();
+        // Without the Xing frame, there is no seek table
+        let mut scanning = Reader::new(&data[417..]).unwrap();
+        for frame in 0..21 {
+            let sample = frame * 1152 + 100;
+            assert_eq!(
+                reader.seek_to_sample(sample),
+                scanning.seek_to_sample(sample)
+            );
+            assert_eq!(reader.byte_offset(), scanning.byte_offset() + 417);
+            assert_eq!(
+                reader.iter.position().frame_index,
+                scanning.iter.position().frame_index + 1
+            );
+        }
+        assert_eq!(reader.seek_to_sample(21 * 1152), None);
+    }
+
+    #[test]
+    fn test_encoder_delay() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        assert_eq!(Reader::new(&data).unwrap().encoder_delay(), Some(576));
+        // Without the LAME tag, the quiet first granule gives it away
+        assert_eq!(
+            Reader::new(&data[417..]).unwrap().encoder_delay(),
+            Some(576)
+        );
+        // Starting in the middle of the sine wave
+        let reader = Reader::new(&data[417 + 731..]).unwrap();
+        assert_eq!(reader.encoder_delay(), None);
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        assert_eq!(Reader::new(&data).unwrap().encoder_delay(), None);
+    }
+
+    #[test]
+    fn test_total_samples() {
+        let data = read("tests/sine_320hz_50ms_vbr.mp3").unwrap();
+        // 3 frames, minus 576 samples of delay and 675 of padding: 50 ms
+        assert_eq!(Reader::new(&data).unwrap().total_samples(), 2205);
+
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        assert_eq!(Reader::new(&data).unwrap().total_samples(), 45 * 576);
+    }
+
+    #[test]
+    fn test_average_bitrate() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let reader = Reader::new(&data).unwrap();
+        let FirstFrame::Vbr(_, vbr_info) = reader.first_frame() else {
+            panic!("no Xing header");
+        };
+        let expected =
+            vbr_info.filesize().unwrap() as f64 * 8.0 / vbr_info.duration().unwrap().as_secs_f64();
+        assert_eq!(reader.average_bitrate(), Some(expected.round() as u32));
+
+        // A CBR file averages to its bitrate
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        let reader = Reader::new(&data).unwrap();
+        let bitrate = reader.first_frame().header().bitrate * 1000;
+        let average = reader.average_bitrate().unwrap();
+        assert!(average.abs_diff(bitrate) < bitrate / 100, "{average}");
+    }
+
+    #[test]
+    fn test_bitrate_mode() {
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        let reader = Reader::new(&data).unwrap();
+        assert_eq!(reader.bitrate_mode(), BitrateMode::Constant);
+
+        // Without the Xing frame, the bitrates give it away
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let reader = Reader::new(&data[417..]).unwrap();
+        assert_eq!(reader.bitrate_mode(), BitrateMode::Variable);
+    }
+
+    #[test]
+    fn test_scan_report() {
+        let data = read("tests/sine_320hz_50ms_vbr.mp3").unwrap();
+        let report = Reader::new(&data).unwrap().scan_report();
+        assert_eq!(report.frames, 3);
+        assert_eq!(report.min_frame_bytes, 130);
+        assert_eq!(report.max_frame_bytes, 731);
+        assert_eq!(report.mean_frame_bytes, (731 + 130 + 365) as f64 / 3.0);
+        assert_eq!(report.bitrates.values().sum::<usize>(), 3);
+
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        let reader = Reader::new(&data).unwrap();
+        let report = reader.scan_report();
+        let bitrate = reader.first_frame().header().bitrate;
+        assert_eq!(report.frames, 45);
+        assert_eq!(
+            report.bitrates.into_iter().collect::<Vec<_>>(),
+            [(bitrate, 45)]
+        );
+        // Padding keeps the average at the nominal bitrate
+        assert!(report.padded_frames > 0);
+        assert!(report.max_frame_bytes - report.min_frame_bytes <= 1);
+    }
+
+    #[test]
+    fn test_seek_by_scanning() {
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        let mut reader = Reader::new(&data).unwrap();
+        let landed = reader.seek_to(Duration::from_millis(100)).unwrap();
+        assert_eq!(landed, Duration::from_secs(3 * 576) / 22050);
+        // The first frame is an audio frame too
+        assert_eq!(reader.by_ref().count(), 45 - 3);
+
+        assert_eq!(reader.seek_to(Duration::from_secs(10)), None);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::Frame;
+
+/// The largest `main_data_begin`, which has 9 bits in MPEG-1 and 8 bits in
+/// the LSF versions.
+const MAX_BEGIN: usize = 511;
+
+/// The bit reservoir: the main data of a frame can start in the main data of
+/// the frames before it, `main_data_begin` bytes before the frame's own.
+///
+/// The buffer is allocated once, at `CAPACITY`, and main data that no later
+/// frame can reach is dropped by moving the rest to its start, so that the
+/// main data of each frame stays contiguous without further allocations.
+#[derive(Debug)]
+pub struct Reservoir {
+    buffer: Vec<u8>,
+    high_water_mark: usize,
+}
+
+impl Default for Reservoir {
+    fn default() -> Self {
+        Reservoir {
+            buffer: Vec::with_capacity(Self::CAPACITY),
+            high_water_mark: 0,
+        }
+    }
+}
+
+impl Reservoir {
+    /// The most the reservoir ever holds: `main_data_begin` bytes of earlier
+    /// frames, and the largest frame, of 320 kbit/s MPEG-1 at 32 kHz or of
+    /// 160 kbit/s MPEG-2.5 at 8 kHz.
+    pub const CAPACITY: usize = MAX_BEGIN + 1441;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bytes allocated for the buffer, which stays at `CAPACITY`.
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Bytes used by the reservoir, including its buffer, which stay at
+    /// `size_of::<Reservoir>() + CAPACITY` for any stream, as for budgeting
+    /// memory statically.
+    pub fn memory_footprint(&self) -> usize {
+        size_of::<Self>() + self.buffer.capacity()
+    }
+
+    /// The most bytes the reservoir has held at once.
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+
+    /// Adds the main data of `frame`, and returns the main data its granules
+    /// are coded in, from `main_data_begin` bytes back up to the end of the
+    /// frame. Returns `None` if that reaches back past what the reservoir
+    /// holds, which happens after `clear`.
+    ///
+    /// For a frame whose main data is all its own, that is borrowed from the
+    /// frame rather than copied, and only as much of it is kept as later
+    /// frames can reach back into.
+    pub fn push<'a>(&'a mut self, frame: &Frame<'a>) -> Option<&'a [u8]> {
+        // Later frames can only reach back MAX_BEGIN bytes
+        let excess = self.buffer.len().saturating_sub(MAX_BEGIN);
+        self.buffer.drain(..excess);
+
+        let begin = frame.side_info.main_data_begin() as usize;
+        if begin == 0 {
+            let main_data = frame.main_data;
+            if main_data.len() >= MAX_BEGIN {
+                self.buffer.clear();
+                self.buffer
+                    .extend_from_slice(&main_data[main_data.len() - MAX_BEGIN..]);
+            } else {
+                self.buffer.extend_from_slice(main_data);
+            }
+            self.high_water_mark = self.high_water_mark.max(self.buffer.len());
+            return Some(main_data);
+        }
+        let start = self.buffer.len().checked_sub(begin);
+        self.buffer.extend_from_slice(frame.main_data);
+        self.high_water_mark = self.high_water_mark.max(self.buffer.len());
+        Some(&self.buffer[start?..])
+    }
+
+    /// Forgets the main data pushed so far, for when frames were lost to
+    /// corruption or skipped by seeking. The main data of earlier frames is
+    /// then missing, so the following frames are undecodable until one whose
+    /// `main_data_begin` only reaches back into frames pushed since, which
+    /// usually takes a few frames. The frames in between are best
+    /// replaced by silence.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+/// Index of the frame to start decoding at for the frame at `target` to
+/// decode fully: early enough for the frames in between to hold the main data
+/// `target` starts in, and one more for the overlap. `offsets` are those of
+/// consecutive frames in `audio`.
+pub(crate) fn priming_start(audio: &[u8], offsets: &[usize], target: usize) -> Option<usize> {
+    let frame_at = |index: usize| Frame::read(&audio[offsets[index]..]).ok();
+    let mut main_data_begin = frame_at(target)?.side_info.main_data_begin() as usize;
+    let mut start = target;
+    while main_data_begin > 0 && start > 0 {
+        start -= 1;
+        main_data_begin = main_data_begin.saturating_sub(frame_at(start)?.main_data.len());
+    }
+    Some(start.saturating_sub(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FrameIter;
+
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_reservoir() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        let frames: Vec<_> = iter.map(|frame| frame.unwrap()).collect();
+
+        let mut reservoir = Reservoir::new();
+        let mut stream = Vec::new();
+        let mut expected = Vec::new();
+        for frame in &frames {
+            let begin = frame.side_info.main_data_begin() as usize;
+            let start = stream.len() - begin;
+            stream.extend_from_slice(frame.main_data);
+            expected.push(stream[start..].to_vec());
+            let main_data = reservoir.push(frame).unwrap();
+            assert_eq!(main_data, &stream[start..]);
+            if begin == 0 {
+                assert_eq!(main_data.as_ptr(), frame.main_data.as_ptr());
+            }
+        }
+
+        // Lose the sixth frame
+        let mut reservoir = Reservoir::new();
+        let mut decodable = Vec::new();
+        for (i, frame) in frames.iter().enumerate() {
+            if i == 5 {
+                reservoir.clear();
+                continue;
+            }
+            if let Some(main_data) = reservoir.push(frame) {
+                assert_eq!(main_data, expected[i]);
+                decodable.push(i);
+            }
+        }
+        assert_eq!(decodable[..5], [0, 1, 2, 3, 4]);
+        // Decoding resumes before the end of the stream
+        assert!(decodable.len() > 6);
+        assert_eq!(reservoir.capacity(), Reservoir::CAPACITY);
+        assert_eq!(
+            reservoir.memory_footprint(),
+            Reservoir::new().memory_footprint()
+        );
+        assert!(reservoir.high_water_mark() <= MAX_BEGIN + 731);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{Frame, FrameHeader};
+
+/// Largest possible frame: MPEG-1 layer II at 384 kbit/s and 32 kHz, padded.
+const MAX_FRAME_BYTES: usize = 144 * 384_000 / 32_000 + 1;
+
+/// Whether a frame consistent with `header` ends right at `start`.
+fn preceded(data: &[u8], start: usize, header: &FrameHeader) -> bool {
+    (start.saturating_sub(MAX_FRAME_BYTES)..start).any(|previous| {
+        FrameHeader::read(&data[previous..]).is_ok_and(|previous_header| {
+            previous + previous_header.frame_bytes == start
+                && previous_header.is_consistent_with(header)
+        })
+    })
+}
+
+/// Offset of the last frame that ends at or before `end`. Like the frames
+/// `FrameIter::resync` finds, it must be followed by a consistent header.
+/// Where no header follows, e.g. before an ID3v1 tag, it must be preceded by
+/// a consistent frame instead, unless it ends the data.
+pub fn find_previous_frame(data: &[u8], end: usize) -> Option<usize> {
+    let mut search_end = end.min(data.len());
+    loop {
+        let start = data[..search_end]
+            .windows(4)
+            .rposition(|bytes| FrameHeader::is_plausible_sync(bytes.try_into().unwrap()))?;
+        if let Ok(header) = FrameHeader::read(&data[start..]) {
+            let frame_end = start + header.frame_bytes;
+            let next = &data[frame_end.min(data.len())..];
+            let valid = next.is_empty()
+                || FrameHeader::read(next).is_ok_and(|next| header.is_consistent_with(&next))
+                || preceded(data, start, &header);
+            if frame_end <= end && valid {
+                return Some(start);
+            }
+        }
+        search_end = start + 3;
+    }
+}
+
+/// Iterates over frames from the end of the data towards its start, e.g.
+/// for scrubbing backwards or for finding the last frame. Bytes between
+/// frames that don't belong to one are skipped, and so are frames whose side
+/// info can't be read.
+#[derive(Debug, Clone)]
+pub struct RevFrameIter<'a> {
+    data: &'a [u8],
+    /// Where the frame yielded last starts.
+    end: usize,
+}
+
+impl<'a> RevFrameIter<'a> {
+    /// Starts with the last frame ending at or before `end`.
+    pub fn new(data: &'a [u8], end: usize) -> Self {
+        RevFrameIter {
+            data,
+            end: end.min(data.len()),
+        }
+    }
+
+    /// Byte offset of the frame yielded last.
+    pub fn byte_offset(&self) -> usize {
+        self.end
+    }
+}
+
+impl<'a> Iterator for RevFrameIter<'a> {
+    /// The offset of each frame and the frame.
+    type Item = (usize, Frame<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = find_previous_frame(self.data, self.end)?;
+            self.end = start;
+            if let Ok(frame) = Frame::read(&self.data[start..]) {
+                return Some((start, frame));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FrameIter;
+
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_rev_frame_iter() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        let mut offsets: Vec<_> = iter
+            .with_positions()
+            .map(|frame| frame.unwrap().position.offset)
+            .collect();
+        // The Xing frame comes last
+        offsets.insert(0, 0);
+        offsets.reverse();
+
+        let frames: Vec<_> = RevFrameIter::new(&data, data.len())
+            .map(|(offset, _)| offset)
+            .collect();
+        assert_eq!(frames, offsets);
+
+        // Starting inside a frame goes to the one before it
+        assert_eq!(find_previous_frame(&data, 3645 + 10), Some(3228));
+        assert_eq!(find_previous_frame(&data, 3645), Some(3228));
+        assert_eq!(find_previous_frame(&data, 100), None);
+    }
+
+    #[test]
+    fn test_rev_frame_iter_trailing_garbage() {
+        let mut data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let len = data.len();
+        data.extend_from_slice(b"TAG\xFF\xFB\x00\x00 and some more bytes");
+        let mut iter = RevFrameIter::new(&data, data.len());
+        assert_eq!(iter.next().unwrap().0, 3645);
+        assert_eq!(iter.byte_offset(), 3645);
+        assert_eq!(find_previous_frame(&data, len), Some(3645));
+    }
+
+    #[test]
+    fn test_rev_frame_iter_broken_frame() {
+        let mut data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        // Window switching with block type 0, in the first granule
+        data[2240 + 10] |= 0x10;
+        data[2240 + 10] &= !0x0C;
+        let offsets: Vec<_> = RevFrameIter::new(&data, data.len())
+            .map(|(offset, _)| offset)
+            .collect();
+        assert_eq!(offsets.len(), 21);
+        assert!(!offsets.contains(&2240));
+        assert_eq!(offsets[..2], [3645, 3228]);
+        assert_eq!(offsets.last(), Some(&0));
+    }
+}
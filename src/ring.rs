@@ -0,0 +1,203 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU32, AtomicUsize, Ordering},
+};
+
+/// The state shared by the two ends of a `sample_ring`.
+#[derive(Debug)]
+struct Shared {
+    /// The bits of each sample, so that the slots can be shared without locks.
+    slots: Box<[AtomicU32]>,
+    /// Samples written and read so far, wrapping around on overflow, so that
+    /// the occupancy is always `written.wrapping_sub(read)`. Only the
+    /// producer advances `written` and only the consumer advances `read`.
+    written: AtomicUsize,
+    read: AtomicUsize,
+    underruns: AtomicUsize,
+}
+
+/// Creates a lock-free single-producer, single-consumer ring of at least
+/// `capacity` samples, for handing decoded audio from a decoding thread to a
+/// realtime audio callback that must not block. The capacity is rounded up to
+/// a power of two, so that the slots stay in order when the counters wrap.
+pub fn sample_ring(capacity: usize) -> (SampleProducer, SampleConsumer) {
+    let shared = Arc::new(Shared {
+        slots: (0..capacity.max(1).next_power_of_two())
+            .map(|_| AtomicU32::new(0))
+            .collect(),
+        written: AtomicUsize::new(0),
+        read: AtomicUsize::new(0),
+        underruns: AtomicUsize::new(0),
+    });
+    (
+        SampleProducer {
+            shared: shared.clone(),
+        },
+        SampleConsumer { shared },
+    )
+}
+
+/// The writing end of a `sample_ring`.
+#[derive(Debug)]
+pub struct SampleProducer {
+    shared: Arc<Shared>,
+}
+
+impl SampleProducer {
+    /// Writes as many of `samples` as there is room for, and returns how
+    /// many that was.
+    pub fn push(&mut self, samples: &[f32]) -> usize {
+        let shared = &*self.shared;
+        let capacity = shared.slots.len();
+        let written = shared.written.load(Ordering::Relaxed);
+        let free = capacity - written.wrapping_sub(shared.read.load(Ordering::Acquire));
+        let count = samples.len().min(free);
+        for (i, sample) in samples[..count].iter().enumerate() {
+            shared.slots[written.wrapping_add(i) % capacity]
+                .store(sample.to_bits(), Ordering::Relaxed);
+        }
+        shared
+            .written
+            .store(written.wrapping_add(count), Ordering::Release);
+        count
+    }
+
+    /// Samples that can be pushed before the ring is full.
+    pub fn free(&self) -> usize {
+        let shared = &*self.shared;
+        shared.slots.len()
+            - shared
+                .written
+                .load(Ordering::Relaxed)
+                .wrapping_sub(shared.read.load(Ordering::Acquire))
+    }
+
+    /// See `SampleConsumer::underruns`.
+    pub fn underruns(&self) -> usize {
+        self.shared.underruns.load(Ordering::Relaxed)
+    }
+}
+
+/// The reading end of a `sample_ring`.
+#[derive(Debug)]
+pub struct SampleConsumer {
+    shared: Arc<Shared>,
+}
+
+impl SampleConsumer {
+    /// Fills `output` with the oldest samples in the ring, and returns how
+    /// many there were. If there weren't enough, the rest of `output` is
+    /// filled with silence and an underrun is counted.
+    pub fn pop(&mut self, output: &mut [f32]) -> usize {
+        let shared = &*self.shared;
+        let capacity = shared.slots.len();
+        let read = shared.read.load(Ordering::Relaxed);
+        let available = shared.written.load(Ordering::Acquire).wrapping_sub(read);
+        let count = output.len().min(available);
+        for (i, sample) in output[..count].iter_mut().enumerate() {
+            *sample = f32::from_bits(
+                shared.slots[read.wrapping_add(i) % capacity].load(Ordering::Relaxed),
+            );
+        }
+        shared
+            .read
+            .store(read.wrapping_add(count), Ordering::Release);
+
+        if count < output.len() {
+            output[count..].fill(0.0);
+            shared.underruns.fetch_add(1, Ordering::Relaxed);
+        }
+        count
+    }
+
+    /// Samples that can be popped.
+    pub fn available(&self) -> usize {
+        let shared = &*self.shared;
+        shared
+            .written
+            .load(Ordering::Acquire)
+            .wrapping_sub(shared.read.load(Ordering::Relaxed))
+    }
+
+    /// Number of `pop` calls that found fewer samples than asked for.
+    pub fn underruns(&self) -> usize {
+        self.shared.underruns.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_ring() {
+        let (mut producer, mut consumer) = sample_ring(4);
+        assert_eq!(producer.push(&[0.1, 0.2, 0.3]), 3);
+        assert_eq!(producer.push(&[0.4, 0.5]), 1);
+        assert_eq!(producer.free(), 0);
+
+        let mut output = [1.0; 3];
+        assert_eq!(consumer.pop(&mut output), 3);
+        assert_eq!(output, [0.1, 0.2, 0.3]);
+        // Wrapping around
+        assert_eq!(producer.push(&[0.5, 0.6]), 2);
+        assert_eq!(consumer.available(), 3);
+        assert_eq!(consumer.pop(&mut output), 3);
+        assert_eq!(output, [0.4, 0.5, 0.6]);
+        assert_eq!(consumer.underruns(), 0);
+
+        assert_eq!(producer.push(&[0.7]), 1);
+        assert_eq!(consumer.pop(&mut output), 1);
+        assert_eq!(output, [0.7, 0.0, 0.0]);
+        assert_eq!(producer.underruns(), 1);
+    }
+
+    #[test]
+    fn test_sample_ring_threads() {
+        let (mut producer, mut consumer) = sample_ring(64);
+        let samples: Vec<f32> = (0..10_000).map(|i| i as f32).collect();
+        let expected = samples.clone();
+        let thread = std::thread::spawn(move || {
+            let mut rest = &samples[..];
+            while !rest.is_empty() {
+                let pushed = producer.push(rest);
+                rest = &rest[pushed..];
+                std::thread::yield_now();
+            }
+        });
+
+        let mut received = Vec::new();
+        let mut output = [0.0; 16];
+        while received.len() < expected.len() {
+            let popped = consumer.pop(&mut output);
+            received.extend_from_slice(&output[..popped]);
+            std::thread::yield_now();
+        }
+        thread.join().unwrap();
+        assert_eq!(received, expected);
+    }
+
+    #[test]
+    fn test_sample_ring_counter_wrap() {
+        let (mut producer, mut consumer) = sample_ring(3);
+        producer
+            .shared
+            .written
+            .store(usize::MAX - 1, Ordering::Relaxed);
+        producer
+            .shared
+            .read
+            .store(usize::MAX - 1, Ordering::Relaxed);
+        assert_eq!(producer.free(), 4);
+
+        let mut output = [0.0; 2];
+        for i in 0..4 {
+            let samples = [i as f32, i as f32 + 0.5];
+            assert_eq!(producer.push(&samples), 2);
+            assert_eq!(consumer.available(), 2);
+            assert_eq!(consumer.pop(&mut output), 2);
+            assert_eq!(output, samples);
+        }
+        assert_eq!(consumer.underruns(), 0);
+    }
+}
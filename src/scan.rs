@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use crate::{FrameHeader, LazyFrame, find_sync};
+
+/// Iterates over frame headers, skipping from one to the next without
+/// reading the side info or main data. This is much faster than `FrameIter`
+/// for counting frames or building indexes. Bytes that don't start a frame
+/// fitting into the data are skipped, as with `FrameIter::resync`.
+#[derive(Debug, Clone)]
+pub struct HeaderIter<'a> {
+    data: &'a [u8],
+    /// Where `data` starts in the stream, for `FrameIter::headers_only`.
+    base: usize,
+    offset: usize,
+    skipped_bytes: usize,
+}
+
+impl<'a> HeaderIter<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self::with_base(data, 0)
+    }
+
+    pub(crate) fn with_base(data: &'a [u8], base: usize) -> Self {
+        HeaderIter {
+            data,
+            base,
+            offset: 0,
+            skipped_bytes: 0,
+        }
+    }
+
+    /// Byte offset of the next header.
+    pub fn byte_offset(&self) -> usize {
+        self.base + self.offset
+    }
+
+    /// Number of bytes skipped so far between frames.
+    pub fn skipped_bytes(&self) -> usize {
+        self.skipped_bytes
+    }
+
+    /// The frames with their offsets, reading their side info only when
+    /// asked to.
+    pub fn lazy_frames(self) -> impl Iterator<Item = (usize, LazyFrame<'a>)> {
+        let (data, base) = (self.data, self.base);
+        self.map(move |(offset, header)| {
+            let start = offset - base;
+            let frame = LazyFrame::read(&data[start..start + header.frame_bytes])
+                .expect("the header was read already");
+            (offset, frame)
+        })
+    }
+}
+
+impl Iterator for HeaderIter<'_> {
+    /// The offset of each frame and its header.
+    type Item = (usize, FrameHeader);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let rest = self
+                .data
+                .get(self.offset..)
+                .filter(|rest| !rest.is_empty())?;
+            match FrameHeader::read(rest) {
+                Ok(header) if header.frame_bytes <= rest.len() => {
+                    let start = self.offset;
+                    self.offset += header.frame_bytes;
+                    return Some((self.base + start, header));
+                }
+                _ => {
+                    let skip = rest
+                        .get(1..)
+                        .and_then(find_sync)
+                        .map_or(rest.len(), |offset| offset + 1);
+                    self.offset += skip;
+                    self.skipped_bytes += skip;
+                }
+            }
+        }
+    }
+}
+
+/// Statistics over the audio frames of a file, see `Reader::scan_report`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanReport {
+    pub frames: usize,
+    /// Number of frames per bitrate, in kbit/s.
+    pub bitrates: BTreeMap<u32, usize>,
+    /// Frame sizes in bytes, including the header. All zero without frames.
+    pub min_frame_bytes: usize,
+    pub max_frame_bytes: usize,
+    pub mean_frame_bytes: f64,
+    /// Number of frames with the padding bit set.
+    pub padded_frames: usize,
+}
+
+impl ScanReport {
+    pub(crate) fn from_headers(headers: impl Iterator<Item = FrameHeader>) -> Self {
+        let mut report = ScanReport {
+            frames: 0,
+            bitrates: BTreeMap::new(),
+            min_frame_bytes: usize::MAX,
+            max_frame_bytes: 0,
+            mean_frame_bytes: 0.0,
+            padded_frames: 0,
+        };
+        let mut total_bytes = 0;
+        for header in headers {
+            report.frames += 1;
+            *report.bitrates.entry(header.bitrate).or_default() += 1;
+            report.min_frame_bytes = report.min_frame_bytes.min(header.frame_bytes);
+            report.max_frame_bytes = report.max_frame_bytes.max(header.frame_bytes);
+            report.padded_frames += header.padding as usize;
+            total_bytes += header.frame_bytes as u64;
+        }
+        if report.frames == 0 {
+            report.min_frame_bytes = 0;
+        } else {
+            report.mean_frame_bytes = total_bytes as f64 / report.frames as f64;
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FrameIter;
+
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_header_iter() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        let mut expected: Vec<_> = iter
+            .with_positions()
+            .map(|frame| {
+                let frame = frame.unwrap();
+                (frame.position.offset, frame.frame.header)
+            })
+            .collect();
+        expected.insert(0, (0, FrameHeader::read(&data).unwrap()));
+        assert_eq!(HeaderIter::new(&data).collect::<Vec<_>>(), expected);
+
+        // A bad header and a truncated final frame are skipped
+        let mut corrupted = b"\xFF\xFB\xF0junk".to_vec();
+        corrupted.extend_from_slice(&data[..3700]);
+        let mut iter = HeaderIter::new(&corrupted);
+        assert_eq!(iter.by_ref().count(), 21);
+        assert_eq!(iter.skipped_bytes(), 7 + 3700 - 3645);
+        assert_eq!(iter.byte_offset(), corrupted.len());
+    }
+}
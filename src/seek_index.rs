@@ -0,0 +1,204 @@
+use crate::{DecodingError, HeaderIter, Position, VbrHeader, probe};
+
+/// A frame start recorded in a `SeekIndex`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeekPoint {
+    /// Where the frame starts, counting offsets from the start of the file.
+    pub position: Position,
+    /// Index of the first sample of the frame, per channel.
+    pub sample: u64,
+}
+
+/// Frame starts of a complete MP3 file, found by reading all of it once, for
+/// seeking precisely in files without a Xing/VBRI seek table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeekIndex {
+    points: Vec<SeekPoint>,
+}
+
+impl SeekIndex {
+    /// Frames between the recorded points in `build`.
+    pub const DEFAULT_INTERVAL: usize = 16;
+
+    /// Records every `DEFAULT_INTERVAL`th audio frame.
+    pub fn build(data: &[u8]) -> Result<Self, DecodingError> {
+        Self::build_with_interval(data, Self::DEFAULT_INTERVAL)
+    }
+
+    /// Records every `interval`th audio frame, starting with the first one.
+    pub fn build_with_interval(data: &[u8], interval: usize) -> Result<Self, DecodingError> {
+        let probe = probe(data)?;
+        let header_frames = (probe.vbr_header != VbrHeader::None) as usize;
+        let mut points = Vec::new();
+        let mut sample = 0;
+        let headers = HeaderIter::new(&data[probe.audio_offset..]);
+        for (index, (offset, header)) in headers.enumerate() {
+            if index % interval.max(1) == 0 {
+                points.push(SeekPoint {
+                    position: Position {
+                        offset: probe.audio_offset + offset,
+                        frame_index: header_frames + index,
+                    },
+                    sample,
+                });
+            }
+            sample += header.samples_per_frame() as u64;
+        }
+        Ok(SeekIndex { points })
+    }
+
+    pub fn points(&self) -> &[SeekPoint] {
+        &self.points
+    }
+
+    /// Serializes the index for caching, see `from_bytes`. Each point is
+    /// stored as LEB128 varints of the differences from the one before it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        write_varint(&mut bytes, self.points.len() as u64);
+        let mut last = (0, 0, 0);
+        for point in &self.points {
+            let current = (
+                point.position.offset as u64,
+                point.position.frame_index as u64,
+                point.sample,
+            );
+            write_varint(&mut bytes, current.0 - last.0);
+            write_varint(&mut bytes, current.1 - last.1);
+            write_varint(&mut bytes, current.2 - last.2);
+            last = current;
+        }
+        bytes
+    }
+
+    /// Reads an index written by `to_bytes`. Returns `None` if `bytes` is
+    /// malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut data = bytes.strip_prefix(MAGIC)?;
+        let len = read_varint(&mut data)?;
+        // Each point takes at least 3 bytes
+        if len > data.len() as u64 / 3 {
+            return None;
+        }
+        let mut points = Vec::with_capacity(len as usize);
+        let mut last = (0_u64, 0_u64, 0_u64);
+        for _ in 0..len {
+            last = (
+                last.0.checked_add(read_varint(&mut data)?)?,
+                last.1.checked_add(read_varint(&mut data)?)?,
+                last.2.checked_add(read_varint(&mut data)?)?,
+            );
+            points.push(SeekPoint {
+                position: Position {
+                    offset: last.0.try_into().ok()?,
+                    frame_index: last.1.try_into().ok()?,
+                },
+                sample: last.2,
+            });
+        }
+        data.is_empty().then_some(SeekIndex { points })
+    }
+
+    /// The last recorded frame starting at or before `sample`. Resume with
+    /// `FrameIter::resume(&data[point.position.offset..], point.position)`.
+    /// Decoding from there needs the frames before it for the bit reservoir,
+    /// so seek to an earlier point if `sample` is close to this one.
+    pub fn lookup(&self, sample: u64) -> Option<SeekPoint> {
+        let after = self.points.partition_point(|point| point.sample <= sample);
+        after.checked_sub(1).map(|index| self.points[index])
+    }
+}
+
+/// Identifies the format of `SeekIndex::to_bytes`, including its version.
+const MAGIC: &[u8] = b"BMSI\x01";
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(data: &mut &[u8]) -> Option<u64> {
+    let mut value = 0_u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data.split_first()?;
+        *data = rest;
+        let bits = (byte & 0x7F) as u64;
+        // The tenth byte only has room for one bit
+        if bits >> (64 - shift).min(63) != 0 {
+            return None;
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FrameIter;
+
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_seek_index() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let index = SeekIndex::build_with_interval(&data, 5).unwrap();
+        // Audio frames 0, 5, 10, 15 and 20
+        assert_eq!(index.points().len(), 5);
+        assert_eq!(
+            index.points()[0],
+            SeekPoint {
+                position: Position {
+                    offset: 417,
+                    frame_index: 1,
+                },
+                sample: 0,
+            }
+        );
+
+        let point = index.lookup(12 * 1152).unwrap();
+        assert_eq!(point.sample, 10 * 1152);
+        assert_eq!(point.position.frame_index, 11);
+        let mut iter = FrameIter::resume(&data[point.position.offset..], point.position);
+        assert_eq!(iter.by_ref().count(), 11);
+        assert_eq!(index.lookup(u64::MAX).unwrap().sample, 20 * 1152);
+    }
+
+    #[test]
+    fn test_seek_index_id3v2() {
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        let index = SeekIndex::build(&data).unwrap();
+        assert_eq!(index.points().len(), 3);
+        // Offsets count the ID3v2 tag
+        assert_eq!(index.points()[0].position.offset, 32);
+        assert_eq!(index.points()[1].sample, 16 * 576);
+        assert_eq!(index.lookup(100).unwrap().sample, 0);
+    }
+
+    #[test]
+    fn test_seek_index_bytes() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let index = SeekIndex::build_with_interval(&data, 1).unwrap();
+        let bytes = index.to_bytes();
+        assert_eq!(SeekIndex::from_bytes(&bytes), Some(index.clone()));
+        // A few bytes per frame
+        assert!(bytes.len() < 5 * index.points().len());
+
+        assert_eq!(SeekIndex::from_bytes(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(SeekIndex::from_bytes(&bytes[1..]), None);
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(SeekIndex::from_bytes(&trailing), None);
+        let mut overlong = MAGIC.to_vec();
+        overlong.extend_from_slice(&[
+            1, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, 0, 0,
+        ]);
+        assert_eq!(SeekIndex::from_bytes(&overlong), None);
+    }
+}
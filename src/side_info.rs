@@ -1,9 +1,7 @@
-use std::u64;
-
 use crate::{
     DecodingError,
-    header::{ChannelMode, FrameHeader},
-    read_bits, read_u32, read_u64,
+    bits::BitReader,
+    header::{ChannelMode, FrameHeader, Version},
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -22,28 +20,25 @@ pub enum Block {
 }
 
 impl Block {
-    fn read_normal(data: u64) -> Result<Self, DecodingError> {
-        todo!();
-        Ok(Block::Normal {
-            table_select: [0, 0, 0],
-            region0_count: 0,
-            region1_count: 0,
-        })
+    fn read_normal(bits: &mut BitReader) -> Self {
+        let table_select = [bits.read(5) as u8, bits.read(5) as u8, bits.read(5) as u8];
+        let region0_count = bits.read(4) as u8;
+        let region1_count = bits.read(3) as u8;
+        Block::Normal {
+            table_select,
+            region0_count,
+            region1_count,
+        }
     }
 
-    fn read_abnormal(data: u64) -> Result<Self, DecodingError> {
-        let block_type = read_bits(data, 34..36) as u8;
+    fn read_abnormal(bits: &mut BitReader) -> Result<Self, DecodingError> {
+        let block_type = bits.read(2) as u8;
         if block_type == 0 {
             return Err(DecodingError::InvalidBlockType);
         }
-        let mixed_block_flag = read_bits(data, 36..37) == 1;
-        let region0_table = read_bits(data, 37..42) as u8;
-        let region1_table = read_bits(data, 42..47) as u8;
-        let table_select = [region0_table, region1_table];
-        let subblock0_gain = read_bits(data, 47..50) as u8;
-        let subblock1_gain = read_bits(data, 50..53) as u8;
-        let subblock2_gain = read_bits(data, 53..56) as u8;
-        let subblock_gain = [subblock0_gain, subblock1_gain, subblock2_gain];
+        let mixed_block_flag = bits.read_bool();
+        let table_select = [bits.read(5) as u8, bits.read(5) as u8];
+        let subblock_gain = [bits.read(3) as u8, bits.read(3) as u8, bits.read(3) as u8];
         Ok(Block::Abnormal {
             block_type,
             mixed_block_flag,
@@ -58,7 +53,7 @@ pub struct Granule {
     part2_3_len: u16,
     big_values: u16,
     global_gain: u8,
-    scalefac_compress: u8,
+    scalefac_compress: u16,
     window_switching: bool,
     block: Block,
     preflag: bool,
@@ -67,21 +62,32 @@ pub struct Granule {
 }
 
 impl Granule {
+    /// Reads the 59 bits of a granule from the MSB of `data` on.
     pub fn read(data: u64) -> Result<Self, DecodingError> {
-        let part2_3_len = read_bits(data, 0..12) as u16;
-        let big_values = read_bits(data, 12..21) as u16;
-        let global_gain = read_bits(data, 21..29) as u8;
-        let scalefac_compress = read_bits(data, 29..33) as u8;
-        let window_switching = read_bits(data, 33..34) == 1;
+        Self::read_bits(&mut BitReader::new(&data.to_be_bytes()), false)
+    }
+
+    /// The MPEG-2/2.5 layout widens scalefac_compress to 9 bits and drops
+    /// preflag, which LSF streams derive from scalefac_compress instead.
+    pub fn read_lsf(data: u64) -> Result<Self, DecodingError> {
+        Self::read_bits(&mut BitReader::new(&data.to_be_bytes()), true)
+    }
+
+    fn read_bits(bits: &mut BitReader, lsf: bool) -> Result<Self, DecodingError> {
+        let part2_3_len = bits.read(12) as u16;
+        let big_values = bits.read(9) as u16;
+        let global_gain = bits.read(8) as u8;
+        let scalefac_compress = bits.read(if lsf { 9 } else { 4 }) as u16;
+        let window_switching = bits.read_bool();
 
         let block = if window_switching {
-            Block::read_abnormal(data)?
+            Block::read_abnormal(bits)?
         } else {
-            Block::read_normal(data)?
+            Block::read_normal(bits)
         };
-        let preflag = read_bits(data, 56..57) == 1;
-        let scalefac_scale = read_bits(data, 57..58) == 1;
-        let count1table_select = read_bits(data, 58..59) == 1;
+        let preflag = !lsf && bits.read_bool();
+        let scalefac_scale = bits.read_bool();
+        let count1table_select = bits.read_bool();
 
         Ok(Granule {
             part2_3_len,
@@ -97,53 +103,386 @@ impl Granule {
     }
 }
 
+impl Granule {
+    /// Length of the scalefactors and Huffman coded data, in bits.
+    pub fn part2_3_len(&self) -> u16 {
+        self.part2_3_len
+    }
+
+    /// Number of value pairs in the big values region.
+    pub fn big_values(&self) -> u16 {
+        self.big_values
+    }
+
+    pub fn global_gain(&self) -> u8 {
+        self.global_gain
+    }
+
+    /// 4 bits in MPEG-1 and 9 bits in MPEG-2/2.5.
+    pub fn scalefac_compress(&self) -> u16 {
+        self.scalefac_compress
+    }
+
+    pub fn window_switching(&self) -> bool {
+        self.window_switching
+    }
+
+    pub fn block(&self) -> &Block {
+        &self.block
+    }
+
+    /// 0 for normal blocks, 1 for start, 2 for short and 3 for end blocks.
+    pub fn block_type(&self) -> u8 {
+        match self.block {
+            Block::Normal { .. } => 0,
+            Block::Abnormal { block_type, .. } => block_type,
+        }
+    }
+
+    /// Always false in MPEG-2/2.5.
+    pub fn preflag(&self) -> bool {
+        self.preflag
+    }
+
+    pub fn scalefac_scale(&self) -> bool {
+        self.scalefac_scale
+    }
+
+    pub fn count1table_select(&self) -> bool {
+        self.count1table_select
+    }
+}
+
+/// Largest value each Huffman table of the big values region can code,
+/// including its linbits.
+const TABLE_MAX_VALUES: [u16; 32] = [
+    0, 1, 2, 2, 15, 3, 3, 5, 5, 5, 7, 7, 7, 15, 15, 15, 16, 18, 22, 30, 78, 270, 1038, 8206, 30,
+    46, 78, 142, 270, 526, 2062, 8206,
+];
+
+impl Granule {
+    /// An upper bound of the magnitude of the dequantized spectral values,
+    /// from the global gain and the largest value the Huffman tables can
+    /// code. Scalefactors and subblock gains only lower the values, so
+    /// they are left out.
+    fn amplitude_bound(&self) -> f64 {
+        let max_value = if self.part2_3_len == 0 {
+            0
+        } else if self.big_values == 0 {
+            // Only the count1 region, of values up to 1
+            1
+        } else {
+            let table_select: &[u8] = match &self.block {
+                Block::Normal { table_select, .. } => table_select,
+                Block::Abnormal { table_select, .. } => table_select,
+            };
+            table_select
+                .iter()
+                .map(|&table| TABLE_MAX_VALUES[table as usize])
+                .max()
+                .unwrap_or(0)
+                .max(1)
+        };
+        (max_value as f64).powf(4.0 / 3.0) * 2_f64.powf((self.global_gain as f64 - 210.0) / 4.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockType {
+    Long,
+    Start,
+    Short,
+    End,
+}
+
+/// Widths of the long block scalefactor bands, in spectral values, for 44.1,
+/// 48 and 32 kHz and then the half and quarter rates.
+const LONG_BAND_WIDTHS: [[u8; 22]; 9] = [
+    [
+        4, 4, 4, 4, 4, 4, 6, 6, 8, 8, 10, 12, 16, 20, 24, 28, 34, 42, 50, 54, 76, 158,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 6, 6, 6, 8, 10, 12, 16, 18, 22, 28, 34, 40, 46, 54, 54, 192,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 6, 6, 8, 10, 12, 16, 20, 24, 30, 38, 46, 56, 68, 84, 102, 26,
+    ],
+    [
+        6, 6, 6, 6, 6, 6, 8, 10, 12, 14, 16, 20, 24, 28, 32, 38, 46, 52, 60, 68, 58, 54,
+    ],
+    [
+        6, 6, 6, 6, 6, 6, 8, 10, 12, 14, 16, 18, 22, 26, 32, 38, 46, 54, 62, 70, 76, 36,
+    ],
+    [
+        6, 6, 6, 6, 6, 6, 8, 10, 12, 14, 16, 20, 24, 28, 32, 38, 46, 52, 60, 68, 58, 54,
+    ],
+    [
+        6, 6, 6, 6, 6, 6, 8, 10, 12, 14, 16, 20, 24, 28, 32, 38, 46, 52, 60, 68, 58, 54,
+    ],
+    [
+        6, 6, 6, 6, 6, 6, 8, 10, 12, 14, 16, 20, 24, 28, 32, 38, 46, 52, 60, 68, 58, 54,
+    ],
+    [
+        12, 12, 12, 12, 12, 12, 16, 20, 24, 28, 32, 40, 48, 56, 64, 76, 90, 2, 2, 2, 2, 2,
+    ],
+];
+
+/// Spectral values per granule and channel.
+const GRANULE_VALUES: usize = 576;
+
+/// Where the first `bands` long block scalefactor bands end.
+fn long_bands_end(header: &FrameHeader, bands: usize) -> usize {
+    let rate = [44100, 48000, 32000, 22050, 24000, 16000, 11025, 12000, 8000]
+        .iter()
+        .position(|&rate| rate == header.sampling_rate)
+        .unwrap_or(0);
+    LONG_BAND_WIDTHS[rate][..bands.min(22)]
+        .iter()
+        .map(|&width| width as usize)
+        .sum()
+}
+
+/// One channel of one granule, with its layout resolved from the side info
+/// and the header. See `Frame::granules`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GranuleChannel<'a> {
+    /// 0 or 1; MPEG-2/2.5 frames only have granule 0.
+    pub granule_index: usize,
+    pub channel: usize,
+    pub granule: &'a Granule,
+    pub block_type: BlockType,
+    /// Whether the lowest bands use long blocks, in a block of short ones.
+    pub mixed_block: bool,
+    /// Huffman table of each big values region. Window switching blocks
+    /// have only two regions, and so no third table.
+    pub table_select: [u8; 3],
+    /// Where each big values region ends, in spectral values. The last end
+    /// is where the count1 region starts.
+    pub region_ends: [usize; 3],
+}
+
+impl<'a> GranuleChannel<'a> {
+    fn new(
+        header: &FrameHeader,
+        granule_index: usize,
+        channel: usize,
+        granule: &'a Granule,
+    ) -> Self {
+        let big_values_end = (granule.big_values as usize * 2).min(GRANULE_VALUES);
+        let (block_type, mixed_block, table_select, region0_end, region1_end) = match granule.block
+        {
+            Block::Normal {
+                table_select,
+                region0_count,
+                region1_count,
+            } => (
+                BlockType::Long,
+                false,
+                table_select,
+                long_bands_end(header, region0_count as usize + 1),
+                long_bands_end(header, region0_count as usize + region1_count as usize + 2),
+            ),
+            Block::Abnormal {
+                block_type,
+                mixed_block_flag,
+                table_select: [table0, table1],
+                ..
+            } => {
+                let block_type = match block_type {
+                    1 => BlockType::Start,
+                    2 => BlockType::Short,
+                    _ => BlockType::End,
+                };
+                // The first region covers 8 long bands, or 3 short ones
+                let region0_end = if block_type == BlockType::Short && !mixed_block_flag {
+                    if header.sampling_rate == 8000 { 72 } else { 36 }
+                } else {
+                    long_bands_end(header, 8)
+                };
+                (
+                    block_type,
+                    mixed_block_flag,
+                    [table0, table1, 0],
+                    region0_end,
+                    GRANULE_VALUES,
+                )
+            }
+        };
+        GranuleChannel {
+            granule_index,
+            channel,
+            granule,
+            block_type,
+            mixed_block,
+            table_select,
+            region_ends: [
+                region0_end.min(big_values_end),
+                region1_end.min(big_values_end),
+                big_values_end,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Channels {
+    Mono(Granule),
+    Stereo([Granule; 2]),
+}
+
+impl Channels {
+    /// The granule of each channel, left first.
+    pub fn granules(&self) -> &[Granule] {
+        match self {
+            Channels::Mono(granule) => std::slice::from_ref(granule),
+            Channels::Stereo(granules) => granules,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SideInfo {
     main_data_begin: u16,
     private_bits: u8,
-    share: u8,
-    granule0: Granule,
-    granule1: Granule,
+    /// Per-channel scalefactor selection info; always zero for LSF streams.
+    share: [u8; 2],
+    granule0: Channels,
+    /// LSF streams carry a single granule per frame.
+    granule1: Option<Channels>,
 }
 
 impl SideInfo {
+    /// How many bytes before this frame's side info its main data starts, in
+    /// the main data of earlier frames (the "bit reservoir").
+    pub fn main_data_begin(&self) -> u16 {
+        self.main_data_begin
+    }
+
+    pub fn private_bits(&self) -> u8 {
+        self.private_bits
+    }
+
+    /// The scalefactor selection info of each channel, one bit per band
+    /// group. Always zero for MPEG-2/2.5.
+    pub fn scfsi(&self) -> [u8; 2] {
+        self.share
+    }
+
+    pub fn granule0(&self) -> &Channels {
+        &self.granule0
+    }
+
+    /// `None` for MPEG-2/2.5, which have one granule per frame.
+    pub fn granule1(&self) -> Option<&Channels> {
+        self.granule1.as_ref()
+    }
+
+    /// An upper bound of the magnitude of the spectral values of all
+    /// granules and channels, as coded, before any stereo processing. Cheap
+    /// to compute, as it just looks at the side info.
+    pub fn amplitude_bound(&self) -> f64 {
+        self.granule_amplitude_bounds().fold(0.0, f64::max)
+    }
+
+    /// `amplitude_bound` of each granule, over its channels.
+    pub(crate) fn granule_amplitude_bounds(&self) -> impl Iterator<Item = f64> + '_ {
+        [Some(&self.granule0), self.granule1.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|channels| match channels {
+                Channels::Mono(granule) => granule.amplitude_bound(),
+                Channels::Stereo([left, right]) => {
+                    left.amplitude_bound().max(right.amplitude_bound())
+                }
+            })
+    }
+
+    /// See `Frame::granules`.
+    pub(crate) fn granule_channels<'a>(
+        &'a self,
+        header: &FrameHeader,
+    ) -> impl Iterator<Item = GranuleChannel<'a>> {
+        let header = *header;
+        [Some(&self.granule0), self.granule1.as_ref()]
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .flat_map(move |(granule_index, channels)| {
+                channels
+                    .granules()
+                    .iter()
+                    .enumerate()
+                    .map(move |(channel, granule)| {
+                        GranuleChannel::new(&header, granule_index, channel, granule)
+                    })
+            })
+    }
+
     pub fn len(header: &FrameHeader) -> usize {
-        if header.channel_mode == ChannelMode::Mono {
-            17
-        } else {
-            32
+        match (header.version, header.channel_mode) {
+            (Version::MPEG1, ChannelMode::Mono) => 17,
+            (Version::MPEG1, _) => 32,
+            (_, ChannelMode::Mono) => 9,
+            (_, _) => 17,
         }
     }
 
-    fn read_mono(side_info_bytes: &[u8]) -> Result<Self, DecodingError> {
-        let mut common_bytes = &side_info_bytes[..4];
-        let common = read_u32(&mut common_bytes)?;
+    fn read_channels(
+        bits: &mut BitReader,
+        stereo: bool,
+        lsf: bool,
+    ) -> Result<Channels, DecodingError> {
+        let left = Granule::read_bits(bits, lsf)?;
+        if stereo {
+            let right = Granule::read_bits(bits, lsf)?;
+            Ok(Channels::Stereo([left, right]))
+        } else {
+            Ok(Channels::Mono(left))
+        }
+    }
 
-        let main_data_begin = read_bits(common, 0..9) as u16;
-        let private_bits = read_bits(common, 9..14) as u8;
-        let share = read_bits(common, 14..18) as u8;
+    fn read_mpeg1(bits: &mut BitReader, stereo: bool) -> Result<Self, DecodingError> {
+        let main_data_begin = bits.read(9) as u16;
+        let (private_bits, share) = if stereo {
+            let private_bits = bits.read(3) as u8;
+            (private_bits, [bits.read(4) as u8, bits.read(4) as u8])
+        } else {
+            let private_bits = bits.read(5) as u8;
+            (private_bits, [bits.read(4) as u8, 0])
+        };
 
-        let mut granule0_bytes = &side_info_bytes[2..10];
-        let mut granule1_bytes = &side_info_bytes[9..17];
+        Ok(SideInfo {
+            main_data_begin,
+            private_bits,
+            share,
+            granule0: Self::read_channels(bits, stereo, false)?,
+            granule1: Some(Self::read_channels(bits, stereo, false)?),
+        })
+    }
 
-        let granule0 = (read_u64(&mut granule0_bytes)? << 2) & (u64::MAX << 5);
-        let granule1 = read_u64(&mut granule1_bytes)? << 5;
+    fn read_lsf(bits: &mut BitReader, stereo: bool) -> Result<Self, DecodingError> {
+        let main_data_begin = bits.read(8) as u16;
+        let private_bits = bits.read(if stereo { 2 } else { 1 }) as u8;
 
         Ok(SideInfo {
             main_data_begin,
             private_bits,
-            share,
-            granule0: Granule::read(granule0)?,
-            granule1: Granule::read(granule1)?,
+            share: [0, 0],
+            granule0: Self::read_channels(bits, stereo, true)?,
+            granule1: None,
         })
     }
 
     pub fn read(header: &FrameHeader, frame_data: &[u8]) -> Result<Self, DecodingError> {
         let offset = SideInfo::len(header);
-        let side_info_bytes = &frame_data[..offset];
-        match header.channel_mode {
-            ChannelMode::Mono => Self::read_mono(side_info_bytes),
-            _ => todo!(),
+        let side_info_bytes = frame_data
+            .get(..offset)
+            .ok_or(DecodingError::UnexpectedEndOfStream)?;
+        let stereo = header.channel_mode != ChannelMode::Mono;
+        let mut bits = BitReader::new(side_info_bytes);
+        match header.version {
+            Version::MPEG1 => Self::read_mpeg1(&mut bits, stereo),
+            Version::MPEG2 | Version::MPEG2_5 => Self::read_lsf(&mut bits, stereo),
         }
     }
 }
@@ -166,6 +505,43 @@ mod tests {
     }
 
     #[test]
+    fn test_amplitude_bound() {
+        let granule = Granule {
+            part2_3_len: 100,
+            big_values: 0,
+            global_gain: 170,
+            scalefac_compress: 0,
+            window_switching: false,
+            block: Block::Normal {
+                table_select: [0, 0, 0],
+                region0_count: 0,
+                region1_count: 0,
+            },
+            preflag: false,
+            scalefac_scale: false,
+            count1table_select: false,
+        };
+        // Values of 1 in the count1 region, at 2^-10
+        assert_eq!(granule.amplitude_bound(), 1.0 / 1024.0);
+        let granule = Granule {
+            big_values: 10,
+            block: Block::Normal {
+                table_select: [1, 13, 16],
+                region0_count: 0,
+                region1_count: 0,
+            },
+            ..granule
+        };
+        assert_eq!(granule.amplitude_bound(), 16_f64.powf(4.0 / 3.0) / 1024.0);
+        let granule = Granule {
+            part2_3_len: 0,
+            ..granule
+        };
+        assert_eq!(granule.amplitude_bound(), 0.0);
+    }
+
+    #[test]
+    #[allow(clippy::unusual_byte_groupings)]
     fn test_mono_side_info() {
         let header = FrameHeader::read(b"\xFF\xFB\x10\xC4").unwrap();
         //                                    <-     common     ->      <- granule0...
@@ -201,8 +577,8 @@ mod tests {
             SideInfo {
                 main_data_begin: 0,
                 private_bits: 0,
-                share: 0,
-                granule0: Granule {
+                share: [0, 0],
+                granule0: Channels::Mono(Granule {
                     part2_3_len: 0b1100010010,
                     big_values: 0b10000,
                     global_gain: 0b10101010,
@@ -217,8 +593,8 @@ mod tests {
                     preflag: true,
                     scalefac_scale: false,
                     count1table_select: false,
-                },
-                granule1: Granule {
+                }),
+                granule1: Some(Channels::Mono(Granule {
                     part2_3_len: 0b10000110110,
                     big_values: 0b1010101,
                     global_gain: 0b10100110,
@@ -233,7 +609,222 @@ mod tests {
                     preflag: false,
                     scalefac_scale: false,
                     count1table_select: false,
-                }
+                }))
+            }
+        );
+    }
+
+    /// Packs a string of '0'/'1' digits into bytes, ignoring any other characters.
+    fn pack_bits(bits: &str) -> Vec<u8> {
+        let bits: Vec<u8> = bits.bytes().filter(|b| matches!(b, b'0' | b'1')).collect();
+        bits.chunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .enumerate()
+                    .fold(0, |acc, (i, bit)| acc | (bit - b'0') << (7 - i))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stereo_side_info() {
+        let header = FrameHeader::read(b"\xFF\xFB\x90\x04").unwrap();
+        let side_info_bytes = pack_bits(concat!(
+            // main_data_begin, private_bits, scfsi (ch0), scfsi (ch1)
+            "000000101 000 1010 0101",
+            // granule0, ch0: long blocks
+            "000001000000 000100000 10010110 0011 0 00001_00010_00011 0100 011 1 0 1",
+            // granule0, ch1: short blocks
+            "000000100000 000010000 10010000 0100 1 10 0 00101_00110 001_010_011 0 1 0",
+            // granule1, ch0
+            "000000000000 000000000 00000000 0000 0 00000_00000_00000 0000 000 0 0 0",
+            // granule1, ch1
+            "111111111111 111111111 11111111 1111 0 11111_11111_11111 1111 111 1 1 1",
+        ));
+        assert_eq!(side_info_bytes.len(), SideInfo::len(&header));
+
+        let side_info = SideInfo::read(&header, &side_info_bytes).unwrap();
+        assert_eq!(side_info.main_data_begin, 5);
+        assert_eq!(side_info.private_bits, 0);
+        assert_eq!(side_info.share, [0b1010, 0b0101]);
+        assert_eq!(side_info.main_data_begin(), 5);
+        assert_eq!(side_info.scfsi(), [0b1010, 0b0101]);
+        let right = &side_info.granule0().granules()[1];
+        assert_eq!(right.part2_3_len(), 32);
+        assert_eq!(right.big_values(), 16);
+        assert_eq!(right.global_gain(), 144);
+        assert_eq!(right.block_type(), 2);
+        assert!(right.window_switching());
+        assert!(right.scalefac_scale());
+        assert_eq!(side_info.granule0().granules()[0].block_type(), 0);
+        assert_eq!(side_info.granule1().unwrap().granules().len(), 2);
+
+        let granules: Vec<_> = side_info.granule_channels(&header).collect();
+        assert_eq!(granules.len(), 4);
+        assert_eq!(granules[0].block_type, BlockType::Long);
+        assert_eq!(granules[0].table_select, [1, 2, 3]);
+        // 5 and 9 bands, before the end of the big values at 64
+        assert_eq!(granules[0].region_ends, [20, 44, 64]);
+        assert_eq!((granules[1].granule_index, granules[1].channel), (0, 1));
+        assert_eq!(granules[1].block_type, BlockType::Short);
+        assert_eq!(granules[1].table_select, [5, 6, 0]);
+        assert_eq!(granules[1].region_ends, [32, 32, 32]);
+        // Big values past the end of the granule
+        assert_eq!(granules[3].region_ends, [162, 576, 576]);
+
+        let Channels::Stereo([left, right]) = side_info.granule0 else {
+            panic!("Expected stereo granule")
+        };
+        assert_eq!(
+            left,
+            Granule {
+                part2_3_len: 64,
+                big_values: 32,
+                global_gain: 150,
+                scalefac_compress: 3,
+                window_switching: false,
+                block: Block::Normal {
+                    table_select: [1, 2, 3],
+                    region0_count: 4,
+                    region1_count: 3,
+                },
+                preflag: true,
+                scalefac_scale: false,
+                count1table_select: true,
+            }
+        );
+        assert_eq!(
+            right,
+            Granule {
+                part2_3_len: 32,
+                big_values: 16,
+                global_gain: 144,
+                scalefac_compress: 4,
+                window_switching: true,
+                block: Block::Abnormal {
+                    block_type: 2,
+                    mixed_block_flag: false,
+                    table_select: [5, 6],
+                    subblock_gain: [1, 2, 3],
+                },
+                preflag: false,
+                scalefac_scale: true,
+                count1table_select: false,
+            }
+        );
+
+        let Some(Channels::Stereo([_, right])) = side_info.granule1 else {
+            panic!("Expected stereo granule")
+        };
+        assert_eq!(right.part2_3_len, 0xFFF);
+        assert_eq!(right.scalefac_compress, 0xF);
+        assert!(right.count1table_select);
+    }
+
+    #[test]
+    fn test_long_band_widths() {
+        for widths in LONG_BAND_WIDTHS {
+            assert_eq!(
+                widths.iter().map(|&width| width as usize).sum::<usize>(),
+                GRANULE_VALUES
+            );
+        }
+    }
+
+    #[test]
+    fn test_real_lsf_side_info() {
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        // Skip the ID3v2 tag
+        let (first, iter) = FrameIter::new(&data[32..]).unwrap();
+        let FirstFrame::Cbr(frame) = first else {
+            panic!("Expected CBR frame")
+        };
+        assert_eq!(frame.header.version, Version::MPEG2);
+        assert_eq!(frame.header.sampling_rate, 22050);
+        assert_eq!(
+            frame.side_info,
+            SideInfo {
+                main_data_begin: 0,
+                private_bits: 0,
+                share: [0, 0],
+                granule0: Channels::Mono(Granule {
+                    part2_3_len: 0,
+                    big_values: 0,
+                    global_gain: 210,
+                    scalefac_compress: 0,
+                    window_switching: false,
+                    block: Block::Normal {
+                        table_select: [0, 0, 0],
+                        region0_count: 0,
+                        region1_count: 0,
+                    },
+                    preflag: false,
+                    scalefac_scale: false,
+                    count1table_select: false,
+                }),
+                granule1: None,
+            }
+        );
+        let frames: Vec<_> = iter.map(|frame| frame.unwrap()).collect();
+        assert_eq!(frames.len(), 44);
+    }
+
+    #[test]
+    fn test_lsf_stereo_side_info() {
+        let header = FrameHeader::read(b"\xFF\xF3\x80\x04").unwrap();
+        let side_info_bytes = pack_bits(concat!(
+            // main_data_begin, private_bits
+            "00010000 01",
+            // ch0: long blocks, 9-bit scalefac_compress
+            "000001000000 000100000 10010110 100000001 0 00001_00010_00011 0100 011 1 0",
+            // ch1: short blocks
+            "000000100000 000010000 10010000 011111111 1 10 1 00101_00110 001_010_011 0 1",
+        ));
+        assert_eq!(side_info_bytes.len(), SideInfo::len(&header));
+
+        let side_info = SideInfo::read(&header, &side_info_bytes).unwrap();
+        assert_eq!(side_info.main_data_begin, 16);
+        assert_eq!(side_info.private_bits, 1);
+        assert_eq!(side_info.granule1, None);
+
+        let Channels::Stereo([left, right]) = side_info.granule0 else {
+            panic!("Expected stereo granule")
+        };
+        assert_eq!(
+            left,
+            Granule {
+                part2_3_len: 64,
+                big_values: 32,
+                global_gain: 150,
+                scalefac_compress: 257,
+                window_switching: false,
+                block: Block::Normal {
+                    table_select: [1, 2, 3],
+                    region0_count: 4,
+                    region1_count: 3,
+                },
+                preflag: false,
+                scalefac_scale: true,
+                count1table_select: false,
+            }
+        );
+        assert_eq!(
+            right,
+            Granule {
+                part2_3_len: 32,
+                big_values: 16,
+                global_gain: 144,
+                scalefac_compress: 255,
+                window_switching: true,
+                block: Block::Abnormal {
+                    block_type: 2,
+                    mixed_block_flag: true,
+                    table_select: [5, 6],
+                    subblock_gain: [1, 2, 3],
+                },
+                preflag: false,
+                scalefac_scale: false,
+                count1table_select: true,
             }
         );
     }
@@ -0,0 +1,272 @@
+use crate::{
+    DecodingError, FirstFrame, Frame, FrameHeader, StreamError, Structure, find_sync, vbr::VbrInfo,
+    vbri::VbriInfo,
+};
+
+/// Parses frames from data that arrives in arbitrary chunks, e.g. over the
+/// network. Partial frames are buffered until the rest of them is fed.
+#[derive(Debug, Default)]
+pub struct StreamParser {
+    buffer: Vec<u8>,
+    /// Length of the frame at the start of `buffer` that was returned last.
+    consumed: usize,
+    /// Stream offset of the start of `buffer`.
+    offset: usize,
+    frame_index: usize,
+    resync: bool,
+    /// The first frame, kept if it carried a Xing/Info or VBRI header.
+    header_frame: Vec<u8>,
+}
+
+enum Step {
+    Frame,
+    Skip(usize),
+    /// The first frame carried a Xing/Info or VBRI header.
+    Header(usize),
+    NeedMore,
+    /// Bytes to drop after returning the error, when resyncing past a frame
+    /// whose header was valid.
+    Error(StreamError, usize),
+}
+
+impl StreamParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See `FrameIter::resync`. Bytes that can't start a frame are dropped
+    /// from the buffer, and so is a frame with a valid header that fails to
+    /// parse, once its error has been returned.
+    pub fn resync(mut self) -> Self {
+        self.resync = true;
+        self
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// The Xing/Info header, if the first frame of the stream carried one.
+    pub fn vbr_info(&self) -> Option<VbrInfo<'_>> {
+        match Frame::read_first(&self.header_frame) {
+            Ok(FirstFrame::Vbr(_, vbr_info)) => Some(vbr_info),
+            _ => None,
+        }
+    }
+
+    /// The VBRI header, if the first frame of the stream carried one.
+    pub fn vbri_info(&self) -> Option<VbriInfo<'_>> {
+        match Frame::read_first(&self.header_frame) {
+            Ok(FirstFrame::Vbri(_, vbri_info)) => Some(vbri_info),
+            _ => None,
+        }
+    }
+
+    /// Bytes that were fed but not yet returned as part of a frame.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len() - self.consumed
+    }
+
+    fn step(&mut self) -> Step {
+        let result = if self.frame_index == 0 {
+            match Frame::read_first_in(&self.buffer) {
+                Ok(FirstFrame::Vbr(header, _) | FirstFrame::Vbri(header, _)) => {
+                    return Step::Header(header.frame_bytes);
+                }
+                Ok(FirstFrame::Cbr(_)) => Ok(()),
+                Err(err) => Err(err),
+            }
+        } else {
+            Frame::read_in(&self.buffer).map(|_| ())
+        };
+
+        match result {
+            Ok(()) => Step::Frame,
+            Err((
+                Structure::FrameHeader | Structure::Frame,
+                DecodingError::UnexpectedEndOfStream,
+            )) => Step::NeedMore,
+            Err((_, err)) if self.resync && err.is_header_error() => {
+                // Keep the last bytes around in case a header starts there
+                let skip = find_sync(&self.buffer[1..])
+                    .map_or(self.buffer.len().saturating_sub(3), |offset| offset + 1);
+                Step::Skip(skip.max(1))
+            }
+            Err((structure, error)) => {
+                let skip = match FrameHeader::read(&self.buffer) {
+                    Ok(header) if self.resync && !error.is_header_error() => {
+                        header.frame_bytes.min(self.buffer.len())
+                    }
+                    _ => 0,
+                };
+                let error = StreamError {
+                    error,
+                    structure,
+                    offset: self.offset,
+                    frame_index: self.frame_index,
+                };
+                Step::Error(error, skip)
+            }
+        }
+    }
+
+    fn drop_front(&mut self, bytes: usize) {
+        self.buffer.drain(..bytes);
+        self.offset += bytes;
+    }
+
+    /// Returns the next complete frame, or `None` if more data needs to be fed
+    /// first. Like `FrameIter`, the same error is returned on every call
+    /// unless resyncing, which skips the data that caused it: at once if it
+    /// can't start a frame, and on the next call for a frame that fails to
+    /// parse.
+    pub fn next_frame(&mut self) -> Option<Result<Frame<'_>, StreamError>> {
+        self.drop_front(self.consumed);
+        self.consumed = 0;
+
+        loop {
+            match self.step() {
+                Step::Frame => break,
+                Step::Skip(bytes) => self.drop_front(bytes),
+                Step::Header(bytes) => {
+                    self.header_frame = self.buffer[..bytes].to_vec();
+                    self.drop_front(bytes);
+                    self.frame_index += 1;
+                }
+                Step::NeedMore => return None,
+                Step::Error(err, skip) => {
+                    if skip > 0 {
+                        self.consumed = skip;
+                        self.frame_index += 1;
+                    }
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        let frame = Frame::read_in(&self.buffer).ok()?;
+        self.consumed = frame.header.frame_bytes;
+        self.frame_index += 1;
+        Some(Ok(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FrameIter;
+
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_stream_parser_chunks() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (_, iter) = FrameIter::new(&data).unwrap();
+        let expected: Vec<_> = iter
+            .map(|frame| frame.unwrap().main_data.to_vec())
+            .collect();
+
+        let mut parser = StreamParser::new();
+        let mut frames = Vec::new();
+        for chunk in data.chunks(100) {
+            parser.feed(chunk);
+            while let Some(frame) = parser.next_frame() {
+                frames.push(frame.unwrap().main_data.to_vec());
+            }
+        }
+
+        assert!(parser.vbr_info().is_some());
+        assert!(parser.vbri_info().is_none());
+        assert_eq!(frames.len(), 21);
+        assert_eq!(frames, expected);
+        assert_eq!(parser.buffered(), 0);
+    }
+
+    #[test]
+    fn test_stream_parser_partial_frame() {
+        let data = read("tests/sine_320hz_50ms_vbr_frame1-3.mp3").unwrap();
+        let mut parser = StreamParser::new();
+
+        parser.feed(&data[..3]);
+        assert!(parser.next_frame().is_none());
+        parser.feed(&data[3..730]);
+        assert!(parser.next_frame().is_none());
+        parser.feed(&data[730..732]);
+        assert_eq!(
+            parser.next_frame().unwrap().unwrap().header.frame_bytes,
+            731
+        );
+        assert!(parser.next_frame().is_none());
+        assert_eq!(parser.buffered(), 1);
+    }
+
+    #[test]
+    fn test_stream_parser_resync() {
+        let data = read("tests/sine_320hz_50ms_vbr_frame1-3.mp3").unwrap();
+        let mut stream = vec![0_u8; 10];
+        stream.extend_from_slice(&data);
+
+        let mut parser = StreamParser::new();
+        parser.feed(&stream);
+        assert_eq!(
+            parser.next_frame().unwrap().err(),
+            Some(StreamError {
+                error: DecodingError::InvalidFrameHeader,
+                structure: Structure::FrameHeader,
+                offset: 0,
+                frame_index: 0,
+            })
+        );
+
+        let mut parser = StreamParser::new().resync();
+        for chunk in stream.chunks(7) {
+            parser.feed(chunk);
+        }
+        let mut frames = 0;
+        while let Some(frame) = parser.next_frame() {
+            frame.unwrap();
+            frames += 1;
+        }
+        assert_eq!(frames, 3);
+    }
+
+    #[test]
+    fn test_stream_parser_resync_broken_frame() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let mut damaged = data.clone();
+        // Window switching with block type 0, in the first granule
+        damaged[2240 + 10] |= 0x10;
+        damaged[2240 + 10] &= !0x0C;
+        let broken = StreamError {
+            error: DecodingError::InvalidBlockType,
+            structure: Structure::SideInfo,
+            offset: 2240,
+            frame_index: 11,
+        };
+
+        // Without resyncing, the error repeats
+        let mut parser = StreamParser::new();
+        parser.feed(&damaged);
+        for _ in 0..10 {
+            parser.next_frame().unwrap().unwrap();
+        }
+        assert_eq!(parser.next_frame().unwrap().err(), Some(broken));
+        assert_eq!(parser.next_frame().unwrap().err(), Some(broken));
+
+        let mut parser = StreamParser::new().resync();
+        let mut frames = 0;
+        let mut errors = Vec::new();
+        for chunk in damaged.chunks(100) {
+            parser.feed(chunk);
+            while let Some(frame) = parser.next_frame() {
+                match frame {
+                    Ok(_) => frames += 1,
+                    Err(err) => errors.push(err),
+                }
+            }
+        }
+        assert_eq!(errors, [broken]);
+        assert_eq!(frames, 20);
+        assert_eq!(parser.buffered(), 0);
+    }
+}
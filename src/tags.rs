@@ -0,0 +1,442 @@
+use std::{ops::Range, time::Duration};
+
+use crate::{
+    FirstFrame, Metadata, Reader,
+    id3::{id3v2_len, leading_id3v2_len},
+    lame::ReplayGain,
+};
+
+const ID3V1_LEN: usize = 128;
+/// The extended ID3v1 tag that precedes the standard one.
+const ID3V1_EXTENDED_LEN: usize = 227;
+const APE_FOOTER_LEN: usize = 32;
+/// Lyrics3v1 limits the lyrics to 5100 bytes.
+const LYRICS3V1_MAX_LEN: usize = 11 + 5100 + 9;
+
+/// Parses a decimal number written as ASCII digits.
+fn ascii_number(digits: &[u8]) -> Option<usize> {
+    str::from_utf8(digits).ok()?.parse().ok()
+}
+
+/// Length of the tag of any of the supported kinds that ends `data`.
+fn trailing_tag_len(data: &[u8]) -> Option<usize> {
+    let len = data.len();
+    // ID3v1, possibly preceded by an extended tag
+    if data.get(len.checked_sub(ID3V1_LEN)?..)?.starts_with(b"TAG") {
+        let extended = len
+            .checked_sub(ID3V1_LEN + ID3V1_EXTENDED_LEN)
+            .is_some_and(|start| data[start..].starts_with(b"TAG+"));
+        return Some(ID3V1_LEN + if extended { ID3V1_EXTENDED_LEN } else { 0 });
+    }
+
+    // APEv2, or APEv1 without the header
+    if let Some(footer) = data.get(len.checked_sub(APE_FOOTER_LEN)?..)
+        && footer.starts_with(b"APETAGEX")
+    {
+        // The size counts the items and the footer, so a smaller one is corrupt
+        let size = u32::from_le_bytes(footer[12..16].try_into().unwrap()) as usize;
+        if size < APE_FOOTER_LEN {
+            return None;
+        }
+        let flags = u32::from_le_bytes(footer[20..24].try_into().unwrap());
+        let header = if flags & 1 << 31 != 0 {
+            APE_FOOTER_LEN
+        } else {
+            0
+        };
+        return Some(size + header).filter(|&tag_len| tag_len <= len);
+    }
+
+    // An ID3v2 tag with a footer, which lets it be found from the end
+    if let Some(footer) = data.get(len.checked_sub(10)?..)
+        && footer.starts_with(b"3DI")
+    {
+        let mut header = *b"ID3\0\0\0\0\0\0\0";
+        header[3..].copy_from_slice(&footer[3..]);
+        return id3v2_len(&header).filter(|&tag_len| tag_len <= len);
+    }
+
+    // Lyrics3v2 ends with the size of the rest of the tag
+    if data.ends_with(b"LYRICS200") {
+        let size = ascii_number(data.get(len.checked_sub(15)?..len - 9)?)?;
+        return Some(size + 15).filter(|&tag_len| tag_len <= len);
+    }
+
+    // Lyrics3v1 has to be searched for its start
+    if data.ends_with(b"LYRICSEND") {
+        let window = &data[len.saturating_sub(LYRICS3V1_MAX_LEN)..];
+        let start = window
+            .windows(11)
+            .rposition(|bytes| bytes == b"LYRICSBEGIN")?;
+        return Some(window.len() - start);
+    }
+
+    None
+}
+
+/// The part of a complete MP3 file that is audio: everything after its
+/// leading ID3v2 tags and before its trailing ID3v1, APE, Lyrics3 and
+/// footered ID3v2 tags.
+pub fn audio_range(data: &[u8]) -> Range<usize> {
+    let start = leading_id3v2_len(data);
+    let mut end = data.len();
+    while let Some(len) = trailing_tag_len(&data[start..end]) {
+        end -= len;
+    }
+    start..end
+}
+
+/// The genres of ID3v1 by their number, as first defined.
+const ID3V1_GENRES: [&str; 80] = [
+    "Blues",
+    "Classic Rock",
+    "Country",
+    "Dance",
+    "Disco",
+    "Funk",
+    "Grunge",
+    "Hip-Hop",
+    "Jazz",
+    "Metal",
+    "New Age",
+    "Oldies",
+    "Other",
+    "Pop",
+    "R&B",
+    "Rap",
+    "Reggae",
+    "Rock",
+    "Techno",
+    "Industrial",
+    "Alternative",
+    "Ska",
+    "Death Metal",
+    "Pranks",
+    "Soundtrack",
+    "Euro-Techno",
+    "Ambient",
+    "Trip-Hop",
+    "Vocal",
+    "Jazz+Funk",
+    "Fusion",
+    "Trance",
+    "Classical",
+    "Instrumental",
+    "Acid",
+    "House",
+    "Game",
+    "Sound Clip",
+    "Gospel",
+    "Noise",
+    "AlternRock",
+    "Bass",
+    "Soul",
+    "Punk",
+    "Space",
+    "Meditative",
+    "Instrumental Pop",
+    "Instrumental Rock",
+    "Ethnic",
+    "Gothic",
+    "Darkwave",
+    "Techno-Industrial",
+    "Electronic",
+    "Pop-Folk",
+    "Eurodance",
+    "Dream",
+    "Southern Rock",
+    "Comedy",
+    "Cult",
+    "Gangsta",
+    "Top 40",
+    "Christian Rap",
+    "Pop/Funk",
+    "Jungle",
+    "Native American",
+    "Cabaret",
+    "New Wave",
+    "Psychadelic",
+    "Rave",
+    "Showtunes",
+    "Trailer",
+    "Lo-Fi",
+    "Tribal",
+    "Acid Punk",
+    "Acid Jazz",
+    "Polka",
+    "Retro",
+    "Musical",
+    "Rock & Roll",
+    "Hard Rock",
+];
+
+/// Reads the fields of a 128-byte ID3v1 or ID3v1.1 tag.
+fn read_id3v1(tag: &[u8]) -> Metadata {
+    // Latin-1, padded with zeros or spaces
+    let text = |bytes: &[u8]| {
+        let text: String = bytes.iter().map(|&byte| byte as char).collect();
+        let text = text.trim_end_matches(['\0', ' ']);
+        (!text.is_empty()).then(|| text.to_string())
+    };
+    // ID3v1.1 keeps the track in the last byte of the comment
+    let track = (tag[125] == 0 && tag[126] != 0).then(|| tag[126].to_string());
+    Metadata {
+        title: text(&tag[3..33]),
+        artist: text(&tag[33..63]),
+        album: text(&tag[63..93]),
+        year: text(&tag[93..97]),
+        track,
+        genre: ID3V1_GENRES
+            .get(tag[127] as usize)
+            .map(|genre| genre.to_string()),
+        ..Metadata::default()
+    }
+}
+
+/// Reads the text items of the APEv2 tag that ends `tag`, including the
+/// ReplayGain items that gain scanners write. Returns `None` if the size in
+/// the footer doesn't fit the tag.
+fn read_ape(tag: &[u8]) -> Option<Metadata> {
+    let footer = tag.get(tag.len().checked_sub(APE_FOOTER_LEN)?..)?;
+    let size = u32::from_le_bytes(footer[12..16].try_into().unwrap()) as usize;
+    let count = u32::from_le_bytes(footer[16..20].try_into().unwrap());
+    if size < APE_FOOTER_LEN || size > tag.len() {
+        return None;
+    }
+    let mut items = &tag[tag.len() - size..tag.len() - APE_FOOTER_LEN];
+
+    let mut metadata = Metadata::default();
+    let mut replay_gain = ReplayGain::default();
+    for _ in 0..count {
+        let Some((len, flags)) = items.get(..8).map(|header| {
+            (
+                u32::from_le_bytes(header[..4].try_into().unwrap()) as usize,
+                u32::from_le_bytes(header[4..8].try_into().unwrap()),
+            )
+        }) else {
+            break;
+        };
+        let Some(key_len) = items[8..].iter().position(|&byte| byte == 0) else {
+            break;
+        };
+        let key = String::from_utf8_lossy(&items[8..8 + key_len]).to_ascii_lowercase();
+        let value_start = 8 + key_len + 1;
+        // The length comes from the file, and may not even fit a `usize`
+        let Some(value_end) = value_start.checked_add(len) else {
+            break;
+        };
+        let Some(value) = items.get(value_start..value_end) else {
+            break;
+        };
+        items = &items[value_end..];
+        // Only UTF-8 text items
+        if flags & 0b110 != 0 {
+            continue;
+        }
+        let value = String::from_utf8_lossy(value).into_owned();
+        // Gains are written like "-6.50 dB"
+        let number = || {
+            value
+                .trim()
+                .trim_end_matches(['d', 'D', 'b', 'B'])
+                .trim()
+                .parse()
+                .ok()
+        };
+        match key.as_str() {
+            "title" => metadata.title = Some(value),
+            "artist" => metadata.artist = Some(value),
+            "album" => metadata.album = Some(value),
+            "year" => metadata.year = Some(value),
+            "track" => metadata.track = Some(value),
+            "genre" => metadata.genre = Some(value),
+            "replaygain_track_gain" => replay_gain.track_gain = number(),
+            "replaygain_album_gain" => replay_gain.album_gain = number(),
+            "replaygain_track_peak" => replay_gain.peak = number(),
+            _ => {}
+        }
+    }
+    if replay_gain != ReplayGain::default() {
+        metadata.replay_gain = Some(replay_gain);
+    }
+    Some(metadata)
+}
+
+/// All the metadata of a complete MP3 file. Each field comes from the first
+/// of these that has it:
+///
+/// - text fields from the leading ID3v2 tag, then trailing ID3v2, APE and
+///   ID3v1 tags, in that order
+/// - ReplayGain from an APE tag, as written by gain scanners after encoding,
+///   then the LAME tag
+/// - the duration from the frames, as `Reader::total_samples` counts them
+pub fn read_metadata(data: &[u8]) -> Metadata {
+    let mut metadata = Metadata::from_id3v2(data).unwrap_or_default();
+
+    let start = leading_id3v2_len(data);
+    let mut end = data.len();
+    let (mut id3v2, mut ape, mut id3v1) = (None, None, None);
+    while let Some(len) = trailing_tag_len(&data[start..end]) {
+        let tag = &data[end - len..end];
+        if tag.ends_with(b"LYRICSEND") || tag.ends_with(b"LYRICS200") {
+            // Lyrics aren't read
+        } else if tag.starts_with(b"ID3") {
+            id3v2 = Metadata::from_id3v2(tag);
+        } else if let Some(footer) = len.checked_sub(APE_FOOTER_LEN).map(|start| &tag[start..])
+            && footer.starts_with(b"APETAGEX")
+        {
+            ape = read_ape(tag);
+        } else if let Some(id3v1_tag) = len.checked_sub(ID3V1_LEN).map(|start| &tag[start..])
+            && id3v1_tag.starts_with(b"TAG")
+        {
+            id3v1 = Some(read_id3v1(id3v1_tag));
+        }
+        end -= len;
+    }
+    for other in [id3v2, ape, id3v1].into_iter().flatten() {
+        metadata.fill_from(other);
+    }
+
+    if let Ok(reader) = Reader::new(data) {
+        if let FirstFrame::Vbr(_, vbr_info) = reader.first_frame()
+            && let Some(replay_gain) = vbr_info.replay_gain()
+            && replay_gain != ReplayGain::default()
+        {
+            metadata.replay_gain = metadata.replay_gain.or(Some(replay_gain));
+        }
+        let sampling_rate = reader.first_frame().header().sampling_rate;
+        metadata.duration = Some(Duration::from_secs(reader.total_samples()) / sampling_rate);
+    }
+    metadata
+}
+
+/// A copy of `data` without its tags, see `audio_range`.
+pub fn strip_tags(data: &[u8]) -> Vec<u8> {
+    data[audio_range(data)].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_audio_range() {
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        let audio_len = data.len() - 32;
+        assert_eq!(audio_range(&data), 32..data.len());
+
+        let mut tagged = data.clone();
+        // Lyrics3v1
+        tagged.extend_from_slice(b"LYRICSBEGINsome lyricsLYRICSEND");
+        // Lyrics3v2
+        tagged.extend_from_slice(b"LYRICSBEGININD00002");
+        tagged.extend_from_slice(b"000019LYRICS200");
+        // APEv2 with a header
+        let mut ape = b"APETAGEX\xD0\x07\0\0\x20\0\0\0\0\0\0\0\0\0\0\x80\0\0\0\0\0\0\0\0".to_vec();
+        ape.extend_from_slice(&ape.clone());
+        tagged.extend_from_slice(&ape);
+        // Extended ID3v1 and ID3v1
+        tagged.extend_from_slice(b"TAG+");
+        tagged.resize(tagged.len() + ID3V1_EXTENDED_LEN - 4, b' ');
+        tagged.extend_from_slice(b"TAG");
+        tagged.resize(tagged.len() + ID3V1_LEN - 3, 0);
+
+        assert_eq!(audio_range(&tagged), 32..32 + audio_len);
+        assert_eq!(strip_tags(&tagged), data[32..]);
+
+        // A footered ID3v2 tag at the end
+        let mut appended = data.clone();
+        appended.extend_from_slice(b"ID3\x04\0\x10\0\0\0\x02ab");
+        appended.extend_from_slice(b"3DI\x04\0\x10\0\0\0\x02");
+        assert_eq!(audio_range(&appended), 32..32 + audio_len);
+    }
+
+    #[test]
+    fn test_audio_range_malformed_ape() {
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        // Sizes of zero and of less than the footer itself aren't tags
+        for size in [0_u8, 10] {
+            let mut tagged = data.clone();
+            tagged.extend_from_slice(b"APETAGEX\xD0\x07\0\0");
+            tagged.extend_from_slice(&[size, 0, 0, 0]);
+            tagged.resize(tagged.len() + 16, 0);
+            assert_eq!(audio_range(&tagged), 32..tagged.len());
+        }
+    }
+
+    #[test]
+    fn test_read_malformed_ape() {
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        for size in [0_u32, 10, 1000] {
+            let mut footer = b"APETAGEX\xD0\x07\0\0".to_vec();
+            footer.extend_from_slice(&size.to_le_bytes());
+            footer.extend_from_slice(b"\x01\0\0\0");
+            footer.resize(APE_FOOTER_LEN, 0);
+            assert_eq!(read_ape(&footer), None);
+
+            let mut tagged = data.clone();
+            tagged.extend_from_slice(&footer);
+            assert_eq!(read_metadata(&tagged).artist, None);
+        }
+        assert_eq!(read_ape(b"APETAGEX"), None);
+
+        // An item claiming more bytes than there are, up to the maximum
+        let mut tag = u32::MAX.to_le_bytes().to_vec();
+        tag.extend_from_slice(b"\0\0\0\0Artist\0Someone");
+        let size = (tag.len() + APE_FOOTER_LEN) as u32;
+        tag.extend_from_slice(b"APETAGEX\xD0\x07\0\0");
+        tag.extend_from_slice(&size.to_le_bytes());
+        tag.extend_from_slice(b"\x01\0\0\0");
+        tag.resize(size as usize, 0);
+        assert_eq!(read_ape(&tag), Some(Metadata::default()));
+    }
+
+    #[test]
+    fn test_read_metadata() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let metadata = read_metadata(&data);
+        assert_eq!(metadata.title, None);
+        assert_eq!(metadata.replay_gain, None);
+        assert_eq!(metadata.duration, Some(Duration::from_millis(500)));
+
+        let mut tagged = Metadata {
+            title: Some("ID3v2".to_string()),
+            ..Metadata::default()
+        }
+        .prepend_to(&data);
+        let mut items = Vec::new();
+        for (key, value) in [
+            ("Artist", "APE"),
+            ("Title", "APE"),
+            ("REPLAYGAIN_TRACK_GAIN", "-6.50 dB"),
+        ] {
+            items.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            items.extend_from_slice(&[0; 4]);
+            items.extend_from_slice(key.as_bytes());
+            items.push(0);
+            items.extend_from_slice(value.as_bytes());
+        }
+        tagged.extend_from_slice(&items);
+        tagged.extend_from_slice(b"APETAGEX\xD0\x07\0\0");
+        tagged.extend_from_slice(&(items.len() as u32 + 32).to_le_bytes());
+        tagged.extend_from_slice(b"\x03\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0");
+        let mut id3v1 = b"TAGID3v1".to_vec();
+        id3v1.resize(63, 0);
+        id3v1.extend_from_slice(b"Album");
+        id3v1.resize(125, b' ');
+        // Track 3, Rock
+        id3v1.extend_from_slice(b"\0\x03\x11");
+        tagged.extend_from_slice(&id3v1);
+
+        let metadata = read_metadata(&tagged);
+        assert_eq!(metadata.title.as_deref(), Some("ID3v2"));
+        assert_eq!(metadata.artist.as_deref(), Some("APE"));
+        assert_eq!(metadata.album.as_deref(), Some("Album"));
+        assert_eq!(metadata.year, None);
+        assert_eq!(metadata.track.as_deref(), Some("3"));
+        assert_eq!(metadata.genre.as_deref(), Some("Rock"));
+        assert_eq!(metadata.replay_gain.unwrap().track_gain, Some(-6.5));
+        assert_eq!(metadata.duration, Some(Duration::from_millis(500)));
+    }
+}
@@ -0,0 +1,246 @@
+use crate::{
+    DecodingError, FirstFrame, Frame, FrameHeader, audio_range, crc::crc_matches, find_sync,
+    id3::id3v2_len, side_info::SideInfo,
+};
+
+/// A problem found by `validate`. Offsets count from the start of the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// Bytes between frames that don't belong to any.
+    Garbage {
+        offset: usize,
+        len: usize,
+    },
+    /// A frame whose side info can't be read.
+    BrokenFrame {
+        offset: usize,
+        error: DecodingError,
+    },
+    CrcMismatch {
+        offset: usize,
+    },
+    /// The data ends partway through the last frame, after `available` of
+    /// its bytes.
+    Truncated {
+        offset: usize,
+        available: usize,
+    },
+    /// The Xing/Info or VBRI header counts a different number of frames than
+    /// the file has.
+    FrameCountMismatch {
+        expected: u32,
+        found: usize,
+    },
+    /// An ID3v2 tag at the start of the file that claims more bytes than the
+    /// file has.
+    TruncatedTag {
+        offset: usize,
+    },
+    /// More than one ID3v2 tag at the start of the file, as left by taggers
+    /// that prepend a new tag without removing the old one.
+    MultipleTags {
+        count: usize,
+    },
+    /// An ID3, ID3v1 or APE tag between frames, as after concatenating
+    /// tagged files.
+    TagInAudio {
+        offset: usize,
+    },
+}
+
+/// The result of `validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Audio frames, not counting a Xing/Info or VBRI frame.
+    pub frames: usize,
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Walks all the frames of a complete MP3 file and reports anything wrong
+/// with it, as for health checks of a media library.
+pub fn validate(data: &[u8]) -> ValidationReport {
+    let mut issues = Vec::new();
+    let range = audio_range(data);
+
+    let mut tags = 0;
+    let mut offset = 0;
+    while let Some(len) = id3v2_len(&data[offset..]) {
+        if offset + len > data.len() {
+            issues.push(Issue::TruncatedTag { offset });
+            break;
+        }
+        tags += 1;
+        offset += len;
+    }
+    if tags > 1 {
+        issues.push(Issue::MultipleTags { count: tags });
+    }
+
+    let mut offset = range.start;
+    let first = Frame::read_first(&data[offset..range.end]).ok();
+    let expected_frames = match &first {
+        Some(FirstFrame::Vbr(header, vbr_info)) => {
+            offset += header.frame_bytes;
+            vbr_info.frames()
+        }
+        Some(FirstFrame::Vbri(header, vbri_info)) => {
+            offset += header.frame_bytes;
+            Some(vbri_info.frames())
+        }
+        _ => None,
+    };
+    let first_header = first.as_ref().map(|first| *first.header());
+
+    let mut frames = 0;
+    while offset < range.end {
+        let rest = &data[offset..range.end];
+        let header = FrameHeader::read(rest).ok().filter(|header| {
+            first_header.is_none_or(|first_header| first_header.is_consistent_with(header))
+        });
+        match header {
+            Some(header) if header.frame_bytes <= rest.len() => {
+                let frame = &rest[..header.frame_bytes];
+                if let Err(error) = SideInfo::read(&header, &frame[header.len()..]) {
+                    issues.push(Issue::BrokenFrame { offset, error });
+                } else if !crc_matches(&header, frame) {
+                    issues.push(Issue::CrcMismatch { offset });
+                }
+                frames += 1;
+                offset += header.frame_bytes;
+                continue;
+            }
+            Some(_) if rest.get(1..).and_then(find_sync).is_none() => {
+                issues.push(Issue::Truncated {
+                    offset,
+                    available: rest.len(),
+                });
+                break;
+            }
+            _ => {}
+        }
+
+        let len = rest
+            .get(1..)
+            .and_then(find_sync)
+            .map_or(rest.len(), |sync| sync + 1);
+        if [&b"ID3"[..], b"TAG", b"APETAGEX"]
+            .iter()
+            .any(|magic| rest.starts_with(magic))
+        {
+            issues.push(Issue::TagInAudio { offset });
+        } else if let Some(Issue::Garbage {
+            offset: garbage_offset,
+            len: garbage_len,
+        }) = issues.last_mut()
+            && *garbage_offset + *garbage_len == offset
+        {
+            *garbage_len += len;
+        } else {
+            issues.push(Issue::Garbage { offset, len });
+        }
+        offset += len;
+    }
+
+    if let Some(expected) = expected_frames
+        && expected as usize != frames
+    {
+        issues.push(Issue::FrameCountMismatch {
+            expected,
+            found: frames,
+        });
+    }
+    ValidationReport { frames, issues }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_validate() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let report = validate(&data);
+        assert_eq!(
+            report,
+            ValidationReport {
+                frames: 21,
+                issues: vec![],
+            }
+        );
+        assert!(report.is_valid());
+
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        assert_eq!(validate(&data).frames, 45);
+        assert!(validate(&data).is_valid());
+    }
+
+    #[test]
+    fn test_validate_corrupted() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let mut corrupted = b"ID3\x04\0\0\0\0\0\0".to_vec();
+        corrupted.extend_from_slice(b"ID3\x04\0\0\0\0\0\0");
+        corrupted.extend_from_slice(&data[..1148]);
+        corrupted.extend_from_slice(b"junk");
+        // A frame with a CRC that doesn't match
+        let mut frame = b"\xFF\xFA\x90\xC4\x00\x00".to_vec();
+        frame.resize(417, 0);
+        corrupted.extend_from_slice(&frame);
+        corrupted.extend_from_slice(b"TAG and more");
+        corrupted.extend_from_slice(&data[3228..3700]);
+
+        let report = validate(&corrupted);
+        assert_eq!(
+            report.issues,
+            vec![
+                Issue::MultipleTags { count: 2 },
+                Issue::Garbage {
+                    offset: 20 + 1148,
+                    len: 4,
+                },
+                Issue::CrcMismatch { offset: 1172 },
+                Issue::TagInAudio { offset: 1172 + 417 },
+                Issue::Truncated {
+                    offset: 1601 + 417,
+                    available: 55,
+                },
+                Issue::FrameCountMismatch {
+                    expected: 21,
+                    found: 3,
+                },
+            ]
+        );
+
+        let mut truncated = b"ID3\x04\0\0\0\x10\0\0".to_vec();
+        truncated.extend_from_slice(&data);
+        assert!(
+            validate(&truncated)
+                .issues
+                .contains(&Issue::TruncatedTag { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn test_validate_malformed_trailing_tag() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        for size in [0_u32, 10] {
+            let mut tagged = data.clone();
+            tagged.extend_from_slice(b"APETAGEX\xD0\x07\0\0");
+            tagged.extend_from_slice(&size.to_le_bytes());
+            tagged.resize(data.len() + 32, 0);
+
+            let report = validate(&tagged);
+            assert_eq!(report.frames, 21);
+            assert_eq!(
+                report.issues,
+                vec![Issue::TagInAudio { offset: data.len() }]
+            );
+        }
+    }
+}
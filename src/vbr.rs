@@ -1,20 +1,32 @@
+use std::time::Duration;
+
 use crate::{
-    DecodingError,
-    header::{FrameHeader, Version},
-    read_u32,
+    DecodingError, HeaderIter,
+    crc::lame_crc16,
+    header::FrameHeader,
+    lame::{LameTag, ReplayGain},
+    probe, read_u32,
     side_info::SideInfo,
 };
 
-#[derive(Debug, Default)]
-pub struct VbrInfo {
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VbrInfo<'a> {
     frames: Option<u32>,
     filesize: Option<u32>,
-    toc: Option<Box<[u8; 100]>>,
+    /// Borrowed from the frame, so that reading the header doesn't allocate.
+    toc: Option<&'a [u8; 100]>,
     vbr_scale: Option<u32>,
+    lame: Option<LameTag>,
+    duration: Option<Duration>,
+    /// Samples per channel in all the audio frames, including the encoder
+    /// delay and padding.
+    samples: Option<u64>,
+    /// Whether the header is tagged "Info", which LAME writes for CBR streams.
+    info: bool,
 }
 
-impl VbrInfo {
-    fn read_info(mut data: &[u8]) -> Result<Self, DecodingError> {
+impl<'a> VbrInfo<'a> {
+    fn read_info(mut data: &'a [u8]) -> Result<Self, DecodingError> {
         let tags = read_u32(&mut data)?;
         let frames = tags & 1 == 1;
         let filesize = tags & 2 == 2;
@@ -23,8 +35,6 @@ impl VbrInfo {
 
         let mut vbr_info = Self::default();
 
-        dbg!(tags);
-
         if frames {
             vbr_info.frames = Some(read_u32(&mut data)?);
         }
@@ -32,35 +42,282 @@ impl VbrInfo {
             vbr_info.filesize = Some(read_u32(&mut data)?);
         }
         if toc {
-            vbr_info.toc = Some(Box::new(
-                data[..100]
-                    .try_into()
-                    .map_err(|_| DecodingError::UnexpectedEndOfStream)?,
-            ));
+            let (toc, rest) = data
+                .split_first_chunk()
+                .ok_or(DecodingError::UnexpectedEndOfStream)?;
+            vbr_info.toc = Some(toc);
+            data = rest;
         }
-        data = &data[100..];
         if vbr_scale {
             vbr_info.vbr_scale = Some(read_u32(&mut data)?);
         }
+        vbr_info.lame = LameTag::read(data).transpose()?;
 
         Ok(vbr_info)
     }
 
-    pub fn read(header: &FrameHeader, data: &[u8]) -> Option<Result<Self, DecodingError>> {
-        let mut data = match header.version {
-            Version::MPEG1 => &data[SideInfo::len(header)..],
-            _ => return Some(Err(DecodingError::UnsupportedVersion)),
+    /// Number of audio frames, excluding the Xing frame itself.
+    pub fn frames(&self) -> Option<u32> {
+        self.frames
+    }
+
+    pub fn filesize(&self) -> Option<u32> {
+        self.filesize
+    }
+
+    /// The seek table: entry `i` is the byte offset at which `i` percent of
+    /// the playback time has elapsed, in 256ths of `filesize`.
+    pub fn toc(&self) -> Option<&'a [u8; 100]> {
+        self.toc
+    }
+
+    pub fn has_toc(&self) -> bool {
+        self.toc.is_some()
+    }
+
+    pub fn is_info(&self) -> bool {
+        self.info
+    }
+
+    pub fn lame(&self) -> Option<&LameTag> {
+        self.lame.as_ref()
+    }
+
+    pub fn replay_gain(&self) -> Option<ReplayGain> {
+        self.lame.as_ref().map(|lame| lame.replay_gain)
+    }
+
+    /// Number of PCM samples per channel, without the encoder delay and
+    /// padding if the LAME tag records them.
+    pub fn total_samples(&self) -> Option<u64> {
+        let samples = self.samples?;
+        Some(match &self.lame {
+            Some(lame) => {
+                samples.saturating_sub(lame.encoder_delay as u64 + lame.encoder_padding as u64)
+            }
+            None => samples,
+        })
+    }
+
+    /// The VBR scale field, whose meaning is up to the encoder. LAME writes
+    /// 100 - 10 × the VBR level (`-V`) - the algorithm quality (`-q`), so
+    /// higher is better.
+    pub fn vbr_scale(&self) -> Option<u32> {
+        self.vbr_scale
+    }
+
+    /// Playback time of the stream, excluding the Xing frame itself.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// Byte offset, relative to the start of the Xing frame, at which the given
+    /// fraction (0.0 to 1.0) of the playback time has elapsed.
+    pub fn byte_offset_for_fraction(&self, fraction: f32) -> Option<usize> {
+        let toc = self.toc.as_ref()?;
+        let filesize = self.filesize? as f32;
+
+        let percent = (fraction * 100.0).clamp(0.0, 100.0);
+        let index = (percent as usize).min(99);
+        let before = toc[index] as f32;
+        let after = toc.get(index + 1).map_or(256.0, |&after| after as f32);
+        let position = before + (after - before) * (percent - index as f32);
+
+        Some((position / 256.0 * filesize) as usize)
+    }
+
+    /// Fraction (0.0 to 1.0) of the playback time that has elapsed at the given
+    /// byte offset, relative to the start of the Xing frame.
+    pub fn fraction_for_byte(&self, byte: usize) -> Option<f32> {
+        let toc = self.toc.as_ref()?;
+        let filesize = self.filesize? as f32;
+
+        let position = (byte as f32 * 256.0 / filesize).clamp(0.0, 256.0);
+        let index = toc
+            .iter()
+            .rposition(|&entry| entry as f32 <= position)
+            .unwrap_or(0);
+        let before = toc[index] as f32;
+        let after = toc.get(index + 1).map_or(256.0, |&after| after as f32);
+        let percent = if after > before {
+            index as f32 + (position - before) / (after - before)
+        } else {
+            index as f32
+        };
+
+        Some((percent / 100.0).min(1.0))
+    }
+
+    /// Playback time that has elapsed at the given byte offset, relative to the
+    /// start of the Xing frame.
+    pub fn time_for_byte(&self, byte: usize) -> Option<Duration> {
+        Some(self.duration?.mul_f64(self.fraction_for_byte(byte)? as f64))
+    }
+
+    pub fn read(header: &FrameHeader, data: &'a [u8]) -> Option<Result<Self, DecodingError>> {
+        // The Xing header takes the place of the main data of the first frame
+        let mut data = data.get(SideInfo::len(header)..)?;
+        let info = match read_u32(&mut data).map(u32::to_be_bytes) {
+            Ok(tag) if &tag == b"Xing" => false,
+            Ok(tag) if &tag == b"Info" => true,
+            _ => return None,
         };
-        if read_u32(&mut data) != Ok(u32::from_be_bytes(*b"Xing")) {
-            return None;
+        let samples_per_frame = header.samples_per_frame() as u64;
+        Some(Self::read_info(data).map(|mut vbr_info| {
+            vbr_info.samples = vbr_info
+                .frames
+                .map(|frames| frames as u64 * samples_per_frame);
+            vbr_info.duration = vbr_info
+                .samples
+                .map(|samples| Duration::from_secs(samples) / header.sampling_rate);
+            vbr_info.info = info;
+            vbr_info
+        }))
+    }
+}
+
+/// Writes Xing and Info frames, for tools that produce new files from the
+/// frames of others.
+#[derive(Debug, Clone, Default)]
+pub struct XingWriter {
+    info: bool,
+    quality: Option<u32>,
+    lame: Option<LameTag>,
+}
+
+impl XingWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tag the header "Info", as LAME does for CBR streams.
+    pub fn info(mut self) -> Self {
+        self.info = true;
+        self
+    }
+
+    /// Sets the VBR scale field; see `VbrInfo::vbr_scale`.
+    pub fn quality(mut self, quality: u32) -> Self {
+        self.quality = Some(quality);
+        self
+    }
+
+    /// Follow the Xing fields with a LAME tag, e.g. for the encoder delay and
+    /// padding. Its music length and tag CRC are filled in when writing.
+    pub fn lame(mut self, tag: LameTag) -> Self {
+        self.lame = Some(tag);
+        self
+    }
+
+    /// Writes a frame describing audio frames of the given sizes, with their
+    /// frame count, byte count and TOC. The music CRC of the LAME tag is
+    /// left as it is, as it covers the contents of the frames; `rewrite`
+    /// computes it. The frame copies the version, sampling rate and channel
+    /// mode of `template`, at the lowest bitrate that fits the header.
+    /// Returns `None` if none does.
+    pub fn write(&self, template: &FrameHeader, frame_sizes: &[usize]) -> Option<Vec<u8>> {
+        // No CRC and no padding
+        let raw = (template.raw() | 1 << 16) & !(1 << 9);
+        let side_info_len = SideInfo::len(template);
+        let lame_len = self.lame.as_ref().map_or(0, |_| LameTag::LEN);
+        let needed = 4 + side_info_len + 4 + 4 + 4 + 4 + 100 + 4 + lame_len;
+        let header = (1..15)
+            .filter_map(|bitrate| {
+                FrameHeader::read(&(raw & !(0xF << 12) | bitrate << 12).to_be_bytes()).ok()
+            })
+            .find(|header| header.frame_bytes >= needed)?;
+
+        let audio_bytes: usize = frame_sizes.iter().sum();
+        let total = header.frame_bytes + audio_bytes;
+        let starts: Vec<_> = frame_sizes
+            .iter()
+            .scan(header.frame_bytes, |start, size| {
+                let frame_start = *start;
+                *start += size;
+                Some(frame_start)
+            })
+            .collect();
+        let toc = (0..100).map(|percent| {
+            starts
+                .get(percent * starts.len() / 100)
+                .map_or(0, |&start| (start * 256 / total).min(255) as u8)
+        });
+
+        let mut frame = header.raw().to_be_bytes().to_vec();
+        frame.resize(4 + side_info_len, 0);
+        frame.extend_from_slice(if self.info { b"Info" } else { b"Xing" });
+        // Frames, bytes, TOC and optionally quality
+        let flags = if self.quality.is_some() { 0xF } else { 0x7 };
+        frame.extend_from_slice(&(flags as u32).to_be_bytes());
+        frame.extend_from_slice(&(frame_sizes.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&(total as u32).to_be_bytes());
+        frame.extend(toc);
+        if let Some(quality) = self.quality {
+            frame.extend_from_slice(&quality.to_be_bytes());
+        }
+        if let Some(lame) = &self.lame {
+            let lame = LameTag {
+                music_length: total as u32,
+                ..lame.clone()
+            };
+            frame.extend_from_slice(&lame.write());
+            // The tag CRC covers the frame up to itself
+            let crc_start = frame.len() - 2;
+            let tag_crc = lame_crc16(&frame[..crc_start]);
+            frame[crc_start..].copy_from_slice(&tag_crc.to_be_bytes());
+        }
+        frame.resize(header.frame_bytes, 0);
+        Some(frame)
+    }
+
+    /// Sets the music CRC of the LAME tag, if there is one, for the given
+    /// audio frames.
+    pub(crate) fn with_music_crc(&self, frames: &[&[u8]]) -> Self {
+        let mut writer = self.clone();
+        if let Some(lame) = &mut writer.lame {
+            lame.music_crc = lame_crc16(&frames.concat());
+        }
+        writer
+    }
+
+    /// Replaces the Xing, Info or VBRI frame of a complete file with a new
+    /// one, or inserts one if the file has none. Tags are kept.
+    pub fn rewrite(&self, data: &[u8]) -> Result<Vec<u8>, DecodingError> {
+        let probe = probe(data)?;
+        let audio = &data[probe.audio_offset..];
+        let (offsets, headers): (Vec<_>, Vec<_>) = HeaderIter::new(audio).unzip();
+        let first_header = headers
+            .first()
+            .ok_or(DecodingError::UnexpectedEndOfStream)?;
+        let frame_sizes: Vec<_> = headers.iter().map(|header| header.frame_bytes).collect();
+        let frames: Vec<_> = offsets
+            .iter()
+            .zip(&frame_sizes)
+            .map(|(offset, size)| &audio[*offset..offset + size])
+            .collect();
+        let header_frame = self
+            .with_music_crc(&frames)
+            .write(first_header, &frame_sizes)
+            .ok_or(DecodingError::UnsupportedBitrate)?;
+
+        let mut output = data[..probe.first_frame_offset].to_vec();
+        output.extend_from_slice(&header_frame);
+        // Only the frames, leaving out anything between them
+        for frame in frames {
+            output.extend_from_slice(frame);
         }
-        Some(Self::read_info(data))
+        let tags_start = offsets
+            .last()
+            .zip(headers.last())
+            .map_or(0, |(offset, header)| offset + header.frame_bytes);
+        output.extend_from_slice(&audio[tags_start..]);
+        Ok(output)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Frame;
+    use crate::{FirstFrame, Frame};
 
     use super::*;
     use std::fs::read;
@@ -68,13 +325,13 @@ mod tests {
     #[test]
     fn test_vbr_info() {
         let data = read("tests/sine_320hz_50ms_vbr_frame0.mp3").unwrap();
-        let (header, frame_data) = Frame::read_header(&data).unwrap();
-        let vbr_info = VbrInfo::read(&header, &frame_data).unwrap().unwrap();
+        let (header, frame_data) = Frame::read_header_in(&data).unwrap();
+        let vbr_info = VbrInfo::read(&header, frame_data).unwrap().unwrap();
         assert_eq!(vbr_info.frames, Some(3));
         assert_eq!(vbr_info.filesize, Some(1643));
         assert_eq!(
             vbr_info.toc,
-            Some(Box::new([
+            Some(&[
                 0, 152, 152, 152, 152, 152, 152, 152, 152, 152, 152, 152, 152, 152, 152, 152, 152,
                 152, 152, 152, 152, 152, 152, 152, 152, 152, 152, 152, 152, 152, 152, 152, 152,
                 152, 179, 179, 179, 179, 179, 179, 179, 179, 179, 179, 179, 179, 179, 179, 179,
@@ -82,8 +339,119 @@ mod tests {
                 179, 179, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
                 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
                 255, 255, 255
-            ]))
+            ])
+        );
+        // The TOC is read in place
+        assert!(
+            data.as_ptr_range()
+                .contains(&vbr_info.toc.unwrap().as_ptr())
+        );
+        assert_eq!(vbr_info.vbr_scale, Some(80));
+        assert_eq!(vbr_info.frames(), Some(3));
+        assert_eq!(vbr_info.filesize(), Some(1643));
+        assert!(vbr_info.has_toc());
+        assert_eq!(vbr_info.toc(), vbr_info.toc);
+        assert_eq!(vbr_info.vbr_scale(), Some(80));
+        assert_eq!(
+            vbr_info.replay_gain(),
+            Some(ReplayGain {
+                peak: None,
+                track_gain: None,
+                album_gain: None,
+            })
         );
+    }
+
+    #[test]
+    fn test_vbr_info_without_toc() {
+        let mut data = read("tests/sine_320hz_50ms_vbr_frame0.mp3").unwrap();
+        // Clear the TOC flag and drop the TOC, keeping the frame length
+        data[0x1c] &= !4;
+        data.drain(0x25..0x25 + 100);
+        data.resize(417, 0);
+
+        let (header, frame_data) = Frame::read_header_in(&data).unwrap();
+        let vbr_info = VbrInfo::read(&header, frame_data).unwrap().unwrap();
+        assert_eq!(vbr_info.frames, Some(3));
+        assert_eq!(vbr_info.filesize, Some(1643));
+        assert_eq!(vbr_info.toc, None);
+        assert!(!vbr_info.has_toc());
         assert_eq!(vbr_info.vbr_scale, Some(80));
+        assert_eq!(&vbr_info.lame().unwrap().encoder, b"LAME3.100");
+
+        // Flagged fields that don't fit are an error
+        let data = read("tests/sine_320hz_50ms_vbr_frame0.mp3").unwrap();
+        let (header, frame_data) = Frame::read_header_in(&data).unwrap();
+        let xing_start = SideInfo::len(&header);
+        for len in [4 + 4 + 4 + 4 + 50, 4 + 4 + 4 + 4 + 100 + 2] {
+            assert!(matches!(
+                VbrInfo::read(&header, &frame_data[..xing_start + len]),
+                Some(Err(DecodingError::UnexpectedEndOfStream))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_toc_mapping() {
+        let data = read("tests/sine_320hz_50ms_vbr_frame0.mp3").unwrap();
+        let (header, frame_data) = Frame::read_header_in(&data).unwrap();
+        let vbr_info = VbrInfo::read(&header, frame_data).unwrap().unwrap();
+
+        // 3 frames of 1152 samples at 44.1 kHz
+        assert_eq!(vbr_info.duration(), Some(Duration::from_nanos(78_367_346)));
+
+        assert_eq!(vbr_info.byte_offset_for_fraction(0.0), Some(0));
+        // Halfway between TOC entries 0 and 152
+        assert_eq!(vbr_info.byte_offset_for_fraction(0.005), Some(487));
+        // TOC entry 179, which is where the second audio frame ends
+        assert_eq!(vbr_info.byte_offset_for_fraction(0.5), Some(1148));
+        assert_eq!(vbr_info.byte_offset_for_fraction(1.0), Some(1643));
+        assert_eq!(vbr_info.byte_offset_for_fraction(2.0), Some(1643));
+
+        assert_eq!(vbr_info.fraction_for_byte(0), Some(0.0));
+        assert_eq!(vbr_info.fraction_for_byte(1643), Some(1.0));
+        assert_eq!(vbr_info.fraction_for_byte(9999), Some(1.0));
+        let fraction = vbr_info.fraction_for_byte(487).unwrap();
+        assert!((fraction - 0.005).abs() < 0.0001);
+
+        assert_eq!(vbr_info.time_for_byte(0), Some(Duration::ZERO));
+        assert_eq!(vbr_info.time_for_byte(1643), vbr_info.duration());
+    }
+
+    #[test]
+    fn test_toc_mapping_without_toc() {
+        let vbr_info = VbrInfo::default();
+        assert_eq!(vbr_info.byte_offset_for_fraction(0.5), None);
+        assert_eq!(vbr_info.fraction_for_byte(100), None);
+        assert_eq!(vbr_info.time_for_byte(100), None);
+    }
+
+    #[test]
+    fn test_xing_writer() {
+        let data = read("tests/sine_320hz_50ms_vbr.mp3").unwrap();
+        let rewritten = XingWriter::new().quality(57).rewrite(&data).unwrap();
+        let FirstFrame::Vbr(header, vbr_info) = Frame::read_first(&rewritten).unwrap() else {
+            panic!("no Xing header");
+        };
+        let xing_len = header.frame_bytes;
+        assert_eq!(rewritten[xing_len..], data[417..]);
+        assert!(!vbr_info.is_info());
+        assert_eq!(vbr_info.frames(), Some(3));
+        assert_eq!(vbr_info.filesize(), Some(rewritten.len() as u32));
+        assert_eq!(vbr_info.vbr_scale(), Some(57));
+        // Where the second audio frame starts, to the precision of the TOC
+        let offset = vbr_info.byte_offset_for_fraction(0.5).unwrap();
+        assert!(offset.abs_diff(xing_len + 731) <= rewritten.len() / 256);
+
+        // Inserted into a file without one
+        let data = read("tests/lavf_id3v2_mpeg2_22050hz.mp3").unwrap();
+        let rewritten = XingWriter::new().info().rewrite(&data).unwrap();
+        let FirstFrame::Vbr(header, vbr_info) = Frame::read_first(&rewritten[32..]).unwrap() else {
+            panic!("no Info header");
+        };
+        assert_eq!(rewritten.len(), data.len() + header.frame_bytes);
+        assert!(vbr_info.is_info());
+        assert_eq!(vbr_info.frames(), Some(45));
+        assert_eq!(vbr_info.vbr_scale(), None);
     }
 }
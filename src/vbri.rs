@@ -0,0 +1,247 @@
+use std::time::Duration;
+
+use crate::{DecodingError, header::FrameHeader, read_u16, read_u32};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VbriInfo<'a> {
+    version: u16,
+    delay: u16,
+    quality: u16,
+    filesize: u32,
+    frames: u32,
+    /// The seek table as stored in the frame, `entry_size` bytes per entry.
+    toc: &'a [u8],
+    entry_size: usize,
+    toc_scale: u32,
+    frames_per_entry: u16,
+    /// The seek table starts after the VBRI frame itself.
+    first_frame_offset: usize,
+    duration: Duration,
+}
+
+impl<'a> VbriInfo<'a> {
+    /// The VBRI header sits at a fixed offset from the start of the frame,
+    /// regardless of the side info length.
+    const OFFSET: usize = 36;
+
+    fn read_info(header: &FrameHeader, mut data: &'a [u8]) -> Result<Self, DecodingError> {
+        let version = read_u16(&mut data)?;
+        let delay = read_u16(&mut data)?;
+        let quality = read_u16(&mut data)?;
+        let filesize = read_u32(&mut data)?;
+        let frames = read_u32(&mut data)?;
+        let toc_entries = read_u16(&mut data)? as usize;
+        let toc_scale = read_u16(&mut data)? as u32;
+        let entry_size = read_u16(&mut data)? as usize;
+        let frames_per_entry = read_u16(&mut data)?;
+
+        if !(1..=4).contains(&entry_size) || data.len() < toc_entries * entry_size {
+            return Err(DecodingError::UnexpectedEndOfStream);
+        }
+
+        Ok(VbriInfo {
+            version,
+            delay,
+            quality,
+            filesize,
+            frames,
+            toc: &data[..toc_entries * entry_size],
+            entry_size,
+            toc_scale,
+            frames_per_entry,
+            first_frame_offset: header.frame_bytes,
+            duration: Duration::from_secs(frames as u64 * header.samples_per_frame() as u64)
+                / header.sampling_rate,
+        })
+    }
+
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    pub fn delay(&self) -> u16 {
+        self.delay
+    }
+
+    pub fn quality(&self) -> u16 {
+        self.quality
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub fn filesize(&self) -> u32 {
+        self.filesize
+    }
+
+    /// Playback time of the stream, excluding the VBRI frame itself.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    fn toc_len(&self) -> usize {
+        self.toc.len() / self.entry_size
+    }
+
+    /// Byte sizes of the seek table segments.
+    fn toc_entries(&self) -> impl Iterator<Item = u32> + '_ {
+        self.toc.chunks_exact(self.entry_size).map(|entry| {
+            entry
+                .iter()
+                .fold(0_u32, |acc, &byte| acc << 8 | byte as u32)
+                .wrapping_mul(self.toc_scale)
+        })
+    }
+
+    /// Frame index range covered by the given seek table segment.
+    fn segment_frames(&self, index: usize) -> (f32, f32) {
+        let start = index as f32 * self.frames_per_entry as f32;
+        let end = (start + self.frames_per_entry as f32).min(self.frames as f32);
+        (start, end.max(start))
+    }
+
+    /// Byte offset, relative to the start of the VBRI frame, at which the given
+    /// fraction (0.0 to 1.0) of the playback time has elapsed.
+    pub fn byte_offset_for_fraction(&self, fraction: f32) -> Option<usize> {
+        if self.toc.is_empty() || self.frames_per_entry == 0 {
+            return None;
+        }
+
+        let target = fraction.clamp(0.0, 1.0) * self.frames as f32;
+        let index = ((target / self.frames_per_entry as f32) as usize).min(self.toc_len() - 1);
+        let (start, end) = self.segment_frames(index);
+        let within = if end > start {
+            ((target - start) / (end - start)).min(1.0)
+        } else {
+            0.0
+        };
+
+        let segment_offset: usize = self
+            .toc_entries()
+            .take(index)
+            .map(|size| size as usize)
+            .sum();
+        let segment = self.toc_entries().nth(index)?;
+        Some(self.first_frame_offset + segment_offset + (segment as f32 * within) as usize)
+    }
+
+    /// Fraction (0.0 to 1.0) of the playback time that has elapsed at the given
+    /// byte offset, relative to the start of the VBRI frame.
+    pub fn fraction_for_byte(&self, byte: usize) -> Option<f32> {
+        if self.toc.is_empty() || self.frames == 0 {
+            return None;
+        }
+
+        let mut offset = self.first_frame_offset;
+        for (index, size) in self.toc_entries().enumerate() {
+            let size = size as usize;
+            if byte < offset + size {
+                let (start, end) = self.segment_frames(index);
+                let within = byte.saturating_sub(offset) as f32 / size as f32;
+                let frame = start + (end - start) * within;
+                return Some(frame / self.frames as f32);
+            }
+            offset += size;
+        }
+
+        Some(1.0)
+    }
+
+    /// Playback time that has elapsed at the given byte offset, relative to the
+    /// start of the VBRI frame.
+    pub fn time_for_byte(&self, byte: usize) -> Option<Duration> {
+        Some(self.duration.mul_f64(self.fraction_for_byte(byte)? as f64))
+    }
+
+    pub fn read(header: &FrameHeader, data: &'a [u8]) -> Option<Result<Self, DecodingError>> {
+        let mut data = data.get(Self::OFFSET - header.len()..)?;
+        if read_u32(&mut data) != Ok(u32::from_be_bytes(*b"VBRI")) {
+            return None;
+        }
+        Some(Self::read_info(header, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FirstFrame, Frame};
+
+    use super::*;
+
+    fn vbri_frame() -> Vec<u8> {
+        // MPEG-1 Layer III, 128 kbps, 44100 Hz, mono: 417 bytes
+        let mut frame = b"\xFF\xFB\x90\xC4".to_vec();
+        frame.resize(VbriInfo::OFFSET, 0);
+        frame.extend_from_slice(b"VBRI");
+        // version, delay, quality
+        frame.extend_from_slice(&[0, 1, 0x04, 0xB1, 0, 75]);
+        // bytes: the VBRI frame plus 4 segments
+        frame.extend_from_slice(&(417_u32 + 400 + 200 + 300 + 100).to_be_bytes());
+        // frames
+        frame.extend_from_slice(&10_u32.to_be_bytes());
+        // entries, scale, entry size, frames per entry
+        frame.extend_from_slice(&[0, 4, 0, 2, 0, 1, 0, 3]);
+        // segment sizes divided by the scale
+        frame.extend_from_slice(&[200, 100, 150, 50]);
+        frame.resize(417, 0);
+        frame
+    }
+
+    #[test]
+    fn test_vbri_info() {
+        let data = vbri_frame();
+        let FirstFrame::Vbri(header, vbri_info) = Frame::read_first(&data).unwrap() else {
+            panic!("Expected VBRI frame")
+        };
+        assert_eq!(header.frame_bytes, 417);
+        assert_eq!(vbri_info.version(), 1);
+        assert_eq!(vbri_info.delay(), 1201);
+        assert_eq!(vbri_info.quality(), 75);
+        assert_eq!(vbri_info.filesize(), 1417);
+        assert_eq!(vbri_info.frames(), 10);
+        assert_eq!(
+            vbri_info.toc_entries().collect::<Vec<_>>(),
+            [400, 200, 300, 100]
+        );
+        // 10 frames of 1152 samples at 44.1 kHz
+        assert_eq!(vbri_info.duration(), Duration::from_nanos(261_224_489));
+    }
+
+    #[test]
+    fn test_vbri_seek() {
+        let data = vbri_frame();
+        let (header, frame_data) = Frame::read_header_in(&data).unwrap();
+        let vbri_info = VbriInfo::read(&header, frame_data).unwrap().unwrap();
+
+        assert_eq!(vbri_info.byte_offset_for_fraction(0.0), Some(417));
+        // Frame 3 starts the second segment
+        assert_eq!(vbri_info.byte_offset_for_fraction(0.3), Some(817));
+        // Halfway through the second segment
+        assert_eq!(vbri_info.byte_offset_for_fraction(0.45), Some(917));
+        // The last segment only covers frame 9
+        assert_eq!(vbri_info.byte_offset_for_fraction(0.95), Some(1367));
+        assert_eq!(vbri_info.byte_offset_for_fraction(1.0), Some(1417));
+
+        assert_eq!(vbri_info.fraction_for_byte(0), Some(0.0));
+        assert_eq!(vbri_info.fraction_for_byte(417), Some(0.0));
+        assert_eq!(vbri_info.fraction_for_byte(817), Some(0.3));
+        assert_eq!(vbri_info.fraction_for_byte(917), Some(0.45));
+        assert_eq!(vbri_info.fraction_for_byte(1417), Some(1.0));
+
+        assert_eq!(vbri_info.time_for_byte(417), Some(Duration::ZERO));
+        assert_eq!(vbri_info.time_for_byte(1417), Some(vbri_info.duration()));
+    }
+
+    #[test]
+    fn test_truncated_vbri_table() {
+        let mut data = vbri_frame();
+        // Claim 400 entries of 1 byte in a 417 byte frame
+        data[VbriInfo::OFFSET + 18..VbriInfo::OFFSET + 20].copy_from_slice(&[1, 144]);
+        let (header, frame_data) = Frame::read_header_in(&data).unwrap();
+        assert!(matches!(
+            VbriInfo::read(&header, frame_data),
+            Some(Err(DecodingError::UnexpectedEndOfStream))
+        ));
+    }
+}
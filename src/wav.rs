@@ -0,0 +1,115 @@
+use std::io::{self, Write};
+
+/// A sample format `write_wav` can write.
+pub trait WavSample: Copy {
+    /// The `wFormatTag` of the fmt chunk.
+    const FORMAT_TAG: u16;
+    const BITS: u16;
+
+    fn write_le(self, out: &mut Vec<u8>);
+}
+
+/// 16-bit integer PCM.
+impl WavSample for i16 {
+    const FORMAT_TAG: u16 = 1;
+    const BITS: u16 = 16;
+
+    fn write_le(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+/// 32-bit float PCM, with full scale at ±1.0.
+impl WavSample for f32 {
+    const FORMAT_TAG: u16 = 3;
+    const BITS: u16 = 32;
+
+    fn write_le(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+/// Writes interleaved samples as a RIFF/WAVE file. Formats other than
+/// integer PCM get the extended fmt chunk and the fact chunk they require.
+/// Returns an `InvalidInput` error if the data doesn't fit into the 4 GiB
+/// that a RIFF file can hold.
+pub fn write_wav<W: Write, S: WavSample>(
+    mut writer: W,
+    samples: &[S],
+    channels: u16,
+    sampling_rate: u32,
+) -> io::Result<()> {
+    let too_long = || io::Error::new(io::ErrorKind::InvalidInput, "too long for a WAV file");
+    let bytes_per_sample = S::BITS / 8;
+    let data_len =
+        u32::try_from(samples.len() * bytes_per_sample as usize).map_err(|_| too_long())?;
+    let pcm = S::FORMAT_TAG == 1;
+    // The fmt chunk, plus the cbSize field and the fact chunk for non-PCM
+    let header_len = if pcm { 4 + 8 + 16 } else { 4 + 8 + 18 + 8 + 4 };
+    let riff_len = data_len.checked_add(header_len + 8).ok_or_else(too_long)?;
+
+    let mut header = Vec::with_capacity(12 + header_len as usize + 8);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&riff_len.to_le_bytes());
+    header.extend_from_slice(b"WAVE");
+
+    header.extend_from_slice(b"fmt ");
+    header.extend_from_slice(&(if pcm { 16_u32 } else { 18 }).to_le_bytes());
+    header.extend_from_slice(&S::FORMAT_TAG.to_le_bytes());
+    header.extend_from_slice(&channels.to_le_bytes());
+    header.extend_from_slice(&sampling_rate.to_le_bytes());
+    let block_align = channels * bytes_per_sample;
+    header.extend_from_slice(&(sampling_rate * block_align as u32).to_le_bytes());
+    header.extend_from_slice(&block_align.to_le_bytes());
+    header.extend_from_slice(&S::BITS.to_le_bytes());
+    if !pcm {
+        // No extension
+        header.extend_from_slice(&0_u16.to_le_bytes());
+        header.extend_from_slice(b"fact");
+        header.extend_from_slice(&4_u32.to_le_bytes());
+        let frames = samples.len() as u32 / channels.max(1) as u32;
+        header.extend_from_slice(&frames.to_le_bytes());
+    }
+
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_len.to_le_bytes());
+    writer.write_all(&header)?;
+
+    let mut data = Vec::with_capacity(data_len as usize);
+    for sample in samples {
+        sample.write_le(&mut data);
+    }
+    writer.write_all(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_wav_i16() {
+        let mut wav = Vec::new();
+        write_wav(&mut wav, &[1_i16, -1, 2, -2], 2, 44100).unwrap();
+        let mut expected = b"RIFF\x2C\0\0\0WAVEfmt \x10\0\0\0\x01\0\x02\0".to_vec();
+        expected.extend_from_slice(&44100_u32.to_le_bytes());
+        expected.extend_from_slice(&(44100_u32 * 4).to_le_bytes());
+        expected.extend_from_slice(b"\x04\0\x10\0data\x08\0\0\0");
+        expected.extend_from_slice(b"\x01\0\xFF\xFF\x02\0\xFE\xFF");
+        assert_eq!(wav, expected);
+    }
+
+    #[test]
+    fn test_write_wav_f32() {
+        let mut wav = Vec::new();
+        write_wav(&mut wav, &[0.5_f32, -1.0, 0.25], 1, 22050).unwrap();
+        assert_eq!(wav.len(), 12 + 8 + 18 + 8 + 4 + 8 + 12);
+        // The RIFF size counts everything after it
+        assert_eq!(wav[4..8], (wav.len() as u32 - 8).to_le_bytes());
+        assert_eq!(wav[20..22], 3_u16.to_le_bytes());
+        assert_eq!(wav[34..36], 32_u16.to_le_bytes());
+        assert_eq!(&wav[38..42], b"fact");
+        assert_eq!(wav[46..50], 3_u32.to_le_bytes());
+        assert_eq!(&wav[50..54], b"data");
+        assert_eq!(wav[58..62], 0.5_f32.to_le_bytes());
+    }
+}
@@ -0,0 +1,146 @@
+use std::io::{self, Write};
+
+use crate::{DecodingError, Frame, FrameHeader, crc::update_crc, side_info::SideInfo};
+
+/// Copies frames to `W` one at a time, as for cutting and joining files.
+/// Frames whose bit reservoir reaches back past the frames written since the
+/// last `break_reservoir` would decode from whatever precedes them in the
+/// output, so they are turned into silence by clearing their side info.
+#[derive(Debug)]
+pub struct FrameWriter<W> {
+    inner: W,
+    recompute_crc: bool,
+    /// Main data of the frames written since the last `break_reservoir`.
+    available: usize,
+    frame_sizes: Vec<usize>,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(inner: W) -> Self {
+        FrameWriter {
+            inner,
+            recompute_crc: false,
+            available: 0,
+            frame_sizes: Vec::new(),
+        }
+    }
+
+    /// Recompute the CRC of every frame that has one, e.g. after editing
+    /// their side info. CRCs are always recomputed for frames that are
+    /// changed here.
+    pub fn recompute_crc(mut self) -> Self {
+        self.recompute_crc = true;
+        self
+    }
+
+    /// Writes a frame. A frame that is a byte longer or shorter than its
+    /// header says, e.g. after dropping or appending an ancillary byte, gets
+    /// its padding bit adjusted to match. Returns an `InvalidData` error for
+    /// a frame with an invalid header or any other length.
+    pub fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        let invalid = |error: DecodingError| io::Error::new(io::ErrorKind::InvalidData, error);
+        let header = FrameHeader::read(frame).map_err(invalid)?;
+        let mut frame = frame.to_vec();
+        let mut changed = false;
+        let header = if header.frame_bytes == frame.len() {
+            header
+        } else {
+            changed = true;
+            // The padding bit
+            frame[2] ^= 0x02;
+            FrameHeader::read(&frame)
+                .ok()
+                .filter(|header| header.frame_bytes == frame.len())
+                .ok_or(invalid(DecodingError::InvalidFrameHeader))?
+        };
+
+        let main_data_begin =
+            Frame::read(&frame).map_or(0, |frame| frame.side_info.main_data_begin() as usize);
+        let side_info = header.len()..header.len() + SideInfo::len(&header);
+        if main_data_begin > self.available {
+            frame[side_info.clone()].fill(0);
+            changed = true;
+        }
+        if changed || self.recompute_crc {
+            update_crc(&header, &mut frame);
+        }
+
+        self.inner.write_all(&frame)?;
+        self.available += frame.len() - side_info.end;
+        self.frame_sizes.push(frame.len());
+        Ok(())
+    }
+
+    /// The next frame can't use the main data of the frames before it, e.g.
+    /// at the start of another file, or after frames were left out.
+    pub fn break_reservoir(&mut self) {
+        self.available = 0;
+    }
+
+    /// Sizes of the frames written so far, e.g. for a `XingWriter`.
+    pub fn frame_sizes(&self) -> &[usize] {
+        &self.frame_sizes
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::HeaderIter;
+
+    use super::*;
+    use std::fs::read;
+
+    #[test]
+    fn test_frame_writer() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let mut writer = FrameWriter::new(Vec::new());
+        for (offset, header) in HeaderIter::new(&data) {
+            writer
+                .write_frame(&data[offset..offset + header.frame_bytes])
+                .unwrap();
+        }
+        assert_eq!(writer.frame_sizes().len(), 22);
+
+        // Without the frames before it, the last frame becomes silent
+        writer.break_reservoir();
+        writer.write_frame(&data[3645..]).unwrap();
+        let output = writer.into_inner();
+        // Frames that decode as they are stay as they are
+        assert_eq!(output[..data.len()], data);
+        let original = Frame::read(&data[3645..]).unwrap();
+        assert_ne!(original.side_info.main_data_begin(), 0);
+        let last = Frame::read(&output[data.len()..]).unwrap();
+        assert_eq!(last.side_info.main_data_begin(), 0);
+        assert_eq!(last.main_data, original.main_data);
+
+        let mut writer = FrameWriter::new(Vec::new());
+        assert_eq!(
+            writer.write_frame(b"junk").unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_frame_writer_padding() {
+        let data = read("tests/sine_440hz_500ms_vbr.mp3").unwrap();
+        let (offset, header) = HeaderIter::new(&data).nth(1).unwrap();
+        let mut frame = data[offset..offset + header.frame_bytes].to_vec();
+        frame.push(0);
+
+        let mut writer = FrameWriter::new(Vec::new());
+        writer.write_frame(&frame).unwrap();
+        let output = writer.into_inner();
+        let written = FrameHeader::read(&output).unwrap();
+        assert_ne!(written.padding, header.padding);
+        assert_eq!(written.frame_bytes, frame.len());
+        assert_eq!(output[4..], frame[4..]);
+
+        frame.push(0);
+        let mut writer = FrameWriter::new(Vec::new());
+        assert!(writer.write_frame(&frame).is_err());
+    }
+}